@@ -1,26 +1,36 @@
 //! Interface to the accelerometer.
 
-use common::Vector3;
-use dimensioned::{si, ucum};
-use errors::{Error, ErrorKind, Result, ResultExt};
-use i2cdev::core::I2CDevice;
-use i2cdev::linux::LinuxI2CDevice;
+use common::{round, Vector3};
+use errors::{Error, Result, Sensor};
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "linux")]
+use linux_embedded_hal::{I2CError, I2cdev};
 use registers;
-use std::ops::{Deref, DerefMut};
+use units::{si, standard_gravity, value};
+use core::ops::{Deref, DerefMut};
+use core::time::Duration;
 
 
 /// The I2C address of the accelerometer.
-const I2C_ADDRESS: u16 = 0x32 >> 1;
+const I2C_ADDRESS: u8 = 0x32 >> 1;
+
+
+/// The sensor reported in errors.
+const SENSOR: Sensor = Sensor::Accelerometer;
 
 
 /// Interface to an LSM303 digital accelerometer.
 pub struct Accelerometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     device: Dev,
     scale: Scale,
     rate: Rate,
+    mode: OperatingMode,
+    auto_scale: Option<AutoScale>,
+    quiet_samples: u32,
+    pending_scale: Option<Scale>,
 }
 
 
@@ -29,6 +39,7 @@ pub type AccelerationVector = Vector3<si::MeterPerSecond2<f64>>;
 
 
 /// Settings for the scale of the acceleration measurement.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Scale {
     /// +/- 2G
     Scale2G,
@@ -41,6 +52,347 @@ pub enum Scale {
 }
 
 
+/// All of the scales, from the smallest range to the largest.
+const SCALES: [Scale; 4] = [
+    Scale::Scale2G,
+    Scale::Scale4G,
+    Scale::Scale8G,
+    Scale::Scale16G,
+];
+
+
+/// Settings for automatic full-scale ranging.
+///
+/// When any axis exceeds `saturation` of the full scale,
+/// the scale is stepped up to the next larger range.
+/// When every axis stays below `fraction` of the next smaller range
+/// for `samples` consecutive readings, the scale is stepped down.
+/// Keeping `fraction` below `saturation` provides hysteresis.
+///
+/// ```no_run
+/// # use lsm303::accelerometer::{Accelerometer, AutoScale};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut accel = Accelerometer::new("/dev/i2c-1")?;
+/// accel.set_auto_scale(Some(AutoScale::default()));
+/// let (sample, scale) = accel.read_acceleration_with_scale()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct AutoScale {
+    /// The fraction of the full scale above which the scale is stepped up.
+    pub saturation: f64,
+    /// The fraction of the smaller full scale under which readings must stay.
+    pub fraction: f64,
+    /// The number of consecutive readings before stepping down.
+    pub samples: u32,
+}
+
+
+impl Default for AutoScale {
+    fn default() -> AutoScale {
+        AutoScale {
+            saturation: 0.95,
+            fraction: 0.75,
+            samples: 10,
+        }
+    }
+}
+
+
+/// Raw output of the accelerometer.
+///
+/// The counts are right justified, so their range depends on the mode.
+pub struct RawAcceleration {
+    /// The output on each axis.
+    pub counts: Vector3<i16>,
+    /// The scale that was active when the counts were read.
+    pub scale: Scale,
+    /// The mode that was active when the counts were read.
+    pub mode: OperatingMode,
+}
+
+
+/// Power and resolution modes of the accelerometer.
+///
+/// See Table 20 and the description of `CTRL_REG4_A` in the LSM303 datasheet.
+/// The default mode is `HighResolution`.
+///
+/// Lower resolution modes draw less current.
+/// Low-power mode also makes the highest data rates available.
+///
+/// ```no_run
+/// # use lsm303::accelerometer::{Accelerometer, OperatingMode};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut accel = Accelerometer::new("/dev/i2c-1")?;
+/// accel.set_mode(OperatingMode::LowPower)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperatingMode {
+    /// Low-power mode (8 bit output)
+    LowPower,
+    /// Normal mode (10 bit output)
+    Normal,
+    /// High resolution mode (12 bit output)
+    HighResolution,
+}
+
+
+/// Modes of the FIFO buffer.
+///
+/// See the description of `FIFO_CTRL_REG_A` in the LSM303 datasheet.
+/// The default mode is `Bypass`.
+pub enum FifoMode {
+    /// The FIFO is not used; only the latest sample is available.
+    Bypass,
+    /// Samples are collected until the FIFO is full, then collection stops.
+    Fifo,
+    /// Samples are collected continuously; the oldest are discarded when full.
+    Stream,
+    /// Stream mode until an interrupt is triggered, then FIFO mode.
+    StreamToFifo,
+}
+
+
+/// Samples drained from the FIFO buffer.
+pub struct FifoData {
+    samples: [AccelerationVector; 32],
+    len: usize,
+    /// The FIFO was full, and samples may have been lost.
+    pub overrun: bool,
+    /// The number of stored samples exceeded the watermark.
+    pub watermark: bool,
+}
+
+
+/// The two inertial interrupt generators.
+pub enum Interrupt {
+    /// Interrupt generator 1 (`INT1_*_A` registers)
+    Int1,
+    /// Interrupt generator 2 (`INT2_*_A` registers)
+    Int2,
+}
+
+
+/// How the enabled events of an interrupt generator are combined.
+pub enum Combination {
+    /// Trigger when any enabled event occurs.
+    ///
+    /// With 6D detection, this detects movement.
+    Or,
+    /// Trigger when all enabled events occur.
+    ///
+    /// With 6D detection, this detects position.
+    And,
+}
+
+
+/// Configuration of an inertial interrupt generator.
+///
+/// See the description of `INT1_CFG_A` in the LSM303 datasheet.
+/// By default, no events are enabled and both the threshold
+/// and the duration are zero.
+///
+/// ```no_run
+/// # extern crate lsm303;
+/// # use lsm303::units::si;
+/// # use lsm303::accelerometer::{Accelerometer, Interrupt, InterruptConfig};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut accel = Accelerometer::new("/dev/i2c-1")?;
+/// let config = InterruptConfig::new()
+///     .x_high(true)
+///     .y_high(true)
+///     .z_high(true)
+///     .threshold(2.0 * si::MPS2)
+///     .duration(0.1 * si::S);
+/// accel.configure_interrupt(Interrupt::Int1, config)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct InterruptConfig {
+    combination: Combination,
+    six_direction: bool,
+    x_high: bool,
+    x_low: bool,
+    y_high: bool,
+    y_low: bool,
+    z_high: bool,
+    z_low: bool,
+    threshold: si::MeterPerSecond2<f64>,
+    duration: si::Second<f64>,
+}
+
+
+/// The decoded contents of `INT1_SOURCE_A` or `INT2_SOURCE_A`.
+#[allow(missing_docs)]
+pub struct InterruptSource {
+    /// One or more interrupts have been generated.
+    pub active: bool,
+    pub x_high: bool,
+    pub x_low: bool,
+    pub y_high: bool,
+    pub y_low: bool,
+    pub z_high: bool,
+    pub z_low: bool,
+}
+
+
+/// Configuration of click and double-click detection.
+///
+/// See the description of `CLICK_CFG_A` in the LSM303 datasheet,
+/// as well as the application notes.
+/// By default, no clicks are enabled and all of the
+/// thresholds and time windows are zero.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # extern crate lsm303;
+/// # use lsm303::units::si;
+/// # use lsm303::accelerometer::{Accelerometer, ClickConfig};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut accel = Accelerometer::new("/dev/i2c-1")?;
+/// let config = ClickConfig::new()
+///     .z_single(true)
+///     .z_double(true)
+///     .threshold(5.0 * si::MPS2)
+///     .time_limit(Duration::from_millis(20))
+///     .latency(Duration::from_millis(100))
+///     .window(Duration::from_millis(300));
+/// accel.configure_click(config)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ClickConfig {
+    x_single: bool,
+    x_double: bool,
+    y_single: bool,
+    y_double: bool,
+    z_single: bool,
+    z_double: bool,
+    threshold: si::MeterPerSecond2<f64>,
+    time_limit: Duration,
+    latency: Duration,
+    window: Duration,
+}
+
+
+/// An axis of the sensor.
+#[allow(missing_docs)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+
+/// The kind of click that was detected.
+pub enum ClickKind {
+    /// A single click
+    Single,
+    /// A double click
+    Double,
+}
+
+
+/// A click detected by the accelerometer.
+pub struct ClickEvent {
+    /// The axis on which the click was detected.
+    pub axis: Axis,
+    /// The click was in the negative direction along the axis.
+    pub negative: bool,
+    /// Whether this was a single or double click.
+    pub kind: ClickKind,
+}
+
+
+/// Modes of the high-pass filter.
+///
+/// See the description of `CTRL_REG2_A` in the LSM303 datasheet.
+pub enum HighPassMode {
+    /// Normal mode; the filter is reset by reading `REFERENCE_A`.
+    NormalWithReset,
+    /// The value of `REFERENCE_A` is used as the reference for filtering.
+    Reference,
+    /// Normal mode
+    Normal,
+    /// The filter is reset when an interrupt event occurs.
+    AutoReset,
+}
+
+
+/// Cutoff frequency settings of the high-pass filter.
+///
+/// The actual frequency is proportional to the data rate.
+pub enum HighPassCutoff {
+    /// HPCF = 00
+    Highest,
+    /// HPCF = 01
+    High,
+    /// HPCF = 10
+    Low,
+    /// HPCF = 11
+    Lowest,
+}
+
+
+/// Configuration of the high-pass filter.
+///
+/// By default, the filter is in `Normal` mode with the `Highest` cutoff,
+/// and it is not applied to anything.
+///
+/// ```no_run
+/// # use lsm303::accelerometer::{Accelerometer, HighPassCutoff, HighPassFilter};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut accel = Accelerometer::new("/dev/i2c-1")?;
+/// // Remove gravity from the output data.
+/// let filter = HighPassFilter::new()
+///     .cutoff(HighPassCutoff::Lowest)
+///     .output(true);
+/// accel.set_high_pass_filter(filter)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HighPassFilter {
+    mode: HighPassMode,
+    cutoff: HighPassCutoff,
+    output: bool,
+    click: bool,
+    interrupt1: bool,
+    interrupt2: bool,
+}
+
+
+/// The decoded contents of `STATUS_REG_A`.
+#[allow(missing_docs)]
+pub struct Status {
+    /// New data overwrote a sample on at least one axis before it was read.
+    pub xyz_overrun: bool,
+    pub x_overrun: bool,
+    pub y_overrun: bool,
+    pub z_overrun: bool,
+    /// New data is available on all axes.
+    pub xyz_available: bool,
+    pub x_available: bool,
+    pub y_available: bool,
+    pub z_available: bool,
+}
+
+
+impl FifoData {
+    /// The stored samples, oldest first.
+    pub fn samples(&self) -> &[AccelerationVector] {
+        &self.samples[..self.len]
+    }
+}
+
+
 /// Available values for the data rate.
 ///
 /// See Table 20 of the LSM303 datasheet.
@@ -53,7 +405,7 @@ pub enum Scale {
 /// ```no_run
 /// # use lsm303::accelerometer::{Accelerometer, Rate};
 /// # fn main() { test().unwrap(); }
-/// # fn test() -> lsm303::Result<()> {
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
 /// let mut accel = Accelerometer::new("/dev/i2c-1")?;
 /// accel.set_rate(Rate::Rate100Hz)?;
 /// # Ok(())
@@ -85,20 +437,261 @@ pub enum Rate {
 }
 
 
-impl Accelerometer<LinuxI2CDevice> {
+impl InterruptConfig {
+    /// Create a configuration with no events enabled.
+    pub fn new() -> InterruptConfig {
+        InterruptConfig {
+            combination: Combination::Or,
+            six_direction: false,
+            x_high: false,
+            x_low: false,
+            y_high: false,
+            y_low: false,
+            z_high: false,
+            z_low: false,
+            threshold: 0.0 * si::MPS2,
+            duration: 0.0 * si::S,
+        }
+    }
+
+    /// Set how the enabled events are combined.
+    pub fn combination(mut self, combination: Combination) -> InterruptConfig {
+        self.combination = combination;
+        self
+    }
+
+    /// Enable 6 direction movement or position detection.
+    pub fn six_direction(mut self, enable: bool) -> InterruptConfig {
+        self.six_direction = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on X above the threshold.
+    pub fn x_high(mut self, enable: bool) -> InterruptConfig {
+        self.x_high = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on X below the threshold.
+    pub fn x_low(mut self, enable: bool) -> InterruptConfig {
+        self.x_low = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on Y above the threshold.
+    pub fn y_high(mut self, enable: bool) -> InterruptConfig {
+        self.y_high = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on Y below the threshold.
+    pub fn y_low(mut self, enable: bool) -> InterruptConfig {
+        self.y_low = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on Z above the threshold.
+    pub fn z_high(mut self, enable: bool) -> InterruptConfig {
+        self.z_high = enable;
+        self
+    }
+
+    /// Enable the event for acceleration on Z below the threshold.
+    pub fn z_low(mut self, enable: bool) -> InterruptConfig {
+        self.z_low = enable;
+        self
+    }
+
+    /// Set the acceleration threshold.
+    ///
+    /// The threshold is converted using the scale that is active
+    /// when the configuration is applied.
+    pub fn threshold(mut self, threshold: si::MeterPerSecond2<f64>) -> InterruptConfig {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the minimum duration of an event before the interrupt is generated.
+    ///
+    /// The duration is converted using the data rate that is active
+    /// when the configuration is applied.
+    pub fn duration(mut self, duration: si::Second<f64>) -> InterruptConfig {
+        self.duration = duration;
+        self
+    }
+}
+
+
+impl Default for InterruptConfig {
+    fn default() -> InterruptConfig {
+        InterruptConfig::new()
+    }
+}
+
+
+impl ClickConfig {
+    /// Create a configuration with no clicks enabled.
+    pub fn new() -> ClickConfig {
+        ClickConfig {
+            x_single: false,
+            x_double: false,
+            y_single: false,
+            y_double: false,
+            z_single: false,
+            z_double: false,
+            threshold: 0.0 * si::MPS2,
+            time_limit: Duration::from_secs(0),
+            latency: Duration::from_secs(0),
+            window: Duration::from_secs(0),
+        }
+    }
+
+    /// Enable single click detection on X.
+    pub fn x_single(mut self, enable: bool) -> ClickConfig {
+        self.x_single = enable;
+        self
+    }
+
+    /// Enable double click detection on X.
+    pub fn x_double(mut self, enable: bool) -> ClickConfig {
+        self.x_double = enable;
+        self
+    }
+
+    /// Enable single click detection on Y.
+    pub fn y_single(mut self, enable: bool) -> ClickConfig {
+        self.y_single = enable;
+        self
+    }
+
+    /// Enable double click detection on Y.
+    pub fn y_double(mut self, enable: bool) -> ClickConfig {
+        self.y_double = enable;
+        self
+    }
+
+    /// Enable single click detection on Z.
+    pub fn z_single(mut self, enable: bool) -> ClickConfig {
+        self.z_single = enable;
+        self
+    }
+
+    /// Enable double click detection on Z.
+    pub fn z_double(mut self, enable: bool) -> ClickConfig {
+        self.z_double = enable;
+        self
+    }
+
+    /// Set the acceleration threshold of a click.
+    ///
+    /// The threshold is converted using the scale that is active
+    /// when the configuration is applied.
+    pub fn threshold(mut self, threshold: si::MeterPerSecond2<f64>) -> ClickConfig {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the maximum time that the acceleration may stay above the
+    /// threshold for the event to count as a click.
+    pub fn time_limit(mut self, time_limit: Duration) -> ClickConfig {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Set the time after the first click during which
+    /// a second click is ignored.
+    pub fn latency(mut self, latency: Duration) -> ClickConfig {
+        self.latency = latency;
+        self
+    }
+
+    /// Set the time after the latency period during which
+    /// a second click completes a double click.
+    pub fn window(mut self, window: Duration) -> ClickConfig {
+        self.window = window;
+        self
+    }
+}
+
+
+impl Default for ClickConfig {
+    fn default() -> ClickConfig {
+        ClickConfig::new()
+    }
+}
+
+
+impl HighPassFilter {
+    /// Create a configuration that does not filter anything.
+    pub fn new() -> HighPassFilter {
+        HighPassFilter {
+            mode: HighPassMode::Normal,
+            cutoff: HighPassCutoff::Highest,
+            output: false,
+            click: false,
+            interrupt1: false,
+            interrupt2: false,
+        }
+    }
+
+    /// Set the mode of the filter.
+    pub fn mode(mut self, mode: HighPassMode) -> HighPassFilter {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the cutoff frequency.
+    pub fn cutoff(mut self, cutoff: HighPassCutoff) -> HighPassFilter {
+        self.cutoff = cutoff;
+        self
+    }
+
+    /// Apply the filter to the output data and the FIFO.
+    pub fn output(mut self, enable: bool) -> HighPassFilter {
+        self.output = enable;
+        self
+    }
+
+    /// Apply the filter to click detection.
+    pub fn click(mut self, enable: bool) -> HighPassFilter {
+        self.click = enable;
+        self
+    }
+
+    /// Apply the filter to interrupt generator 1.
+    pub fn interrupt1(mut self, enable: bool) -> HighPassFilter {
+        self.interrupt1 = enable;
+        self
+    }
+
+    /// Apply the filter to interrupt generator 2.
+    pub fn interrupt2(mut self, enable: bool) -> HighPassFilter {
+        self.interrupt2 = enable;
+        self
+    }
+}
+
+
+impl Default for HighPassFilter {
+    fn default() -> HighPassFilter {
+        HighPassFilter::new()
+    }
+}
+
+
+#[cfg(feature = "linux")]
+impl Accelerometer<I2cdev> {
     /// Initialize the accelerometer for a Linux I2C device.
     ///
     /// ```
     /// # use lsm303::Accelerometer;
     /// let sensor = Accelerometer::new("/dev/i2c-1");
     /// ```
-    pub fn new<Path>(path: Path) -> Result<Accelerometer<LinuxI2CDevice>>
+    pub fn new<Path>(path: Path) -> Result<Accelerometer<I2cdev>, I2CError>
     where
         Path: AsRef<::std::path::Path>,
     {
-        let device = LinuxI2CDevice::new(&path, I2C_ADDRESS).chain_err(|| {
-            ErrorKind::FailedToOpenDevice
-        })?;
+        let device = I2cdev::new(&path).map_err(|e| Error::FailedToOpenDevice(e.into()))?;
 
         Accelerometer::from_i2c_device(device)
     }
@@ -107,41 +700,39 @@ impl Accelerometer<LinuxI2CDevice> {
 
 impl<Dev> Accelerometer<Dev>
 where
-    Dev: I2CDevice,
-    Error: From<Dev::Error>,
-    Dev::Error: Send + 'static,
+    Dev: I2c,
 {
     /// Initialize the accelerometer, given an open I2C device.
     ///
     /// The opening of the device is platform specific,
     /// but initialization of the sensor is not.
     /// Prefer to use `Accelerometer::new`, unless you are using an
-    /// implementation of `I2CDevice` that is not covered by this crate.
+    /// implementation of `I2c` that is not covered by this crate.
     ///
     /// ```no_run
     /// # extern crate lsm303;
     /// # use lsm303::Accelerometer;
-    /// # extern crate i2cdev;
-    /// # use i2cdev::linux::LinuxI2CDevice;
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
-    /// let device = LinuxI2CDevice::new("/dev/i2c-1", 0x32 >> 1)?;
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let device = I2cdev::new("/dev/i2c-1")?;
     /// let sensor = Accelerometer::from_i2c_device(device)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn from_i2c_device(mut device: Dev) -> Result<Accelerometer<Dev>> {
+    pub fn from_i2c_device(mut device: Dev) -> Result<Accelerometer<Dev>, Dev::Error> {
         use registers::{CTRL_REG1_A, CtrlReg1A, CTRL_REG4_A, CtrlReg4A};
 
         // Set data rate to 10 Hz, enable all axes.
         type R = CtrlReg1A;
         let ctrl_reg1_a = R::ODR1 | R::Zen | R::Yen | R::Xen;
-        write_register!(device, CTRL_REG1_A, ctrl_reg1_a)?;
+        write_register!(device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, ctrl_reg1_a)?;
 
         // Enable high resolution output mode.
-        let mut ctrl_reg4_a = read_register!(device, CTRL_REG4_A, CtrlReg4A)?;
+        let mut ctrl_reg4_a = read_register!(device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, CtrlReg4A)?;
         ctrl_reg4_a.insert(CtrlReg4A::HR);
-        write_register!(device, CTRL_REG4_A, ctrl_reg4_a)?;
+        write_register!(device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, ctrl_reg4_a)?;
 
         // Default scale is +/- 2G
         let scale = Scale::Scale2G;
@@ -149,10 +740,17 @@ where
         // Default rate
         let rate = Rate::Rate10Hz;
 
+        // Low-power mode is disabled and high resolution is enabled above.
+        let mode = OperatingMode::HighResolution;
+
         let accelerometer = Accelerometer {
             device,
             scale,
             rate,
+            mode,
+            auto_scale: None,
+            quiet_samples: 0,
+            pending_scale: None,
         };
         Ok(accelerometer)
     }
@@ -162,7 +760,7 @@ where
     /// ```no_run
     /// # use lsm303::Accelerometer;
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
     /// let accel = sensor.read_acceleration()?;
     /// println!("Acceleration: ({}, {}, {})",
@@ -170,43 +768,156 @@ where
     /// # Ok(())
     /// # }
     /// ```
-    pub fn read_acceleration(&mut self) -> Result<AccelerationVector> {
-        use byteorder::{LittleEndian, ReadBytesExt};
-        use dimensioned::f64prefixes::MILLI;
-        use std::io::Cursor;
-
-        let data = self.device.smbus_read_i2c_block_data(
-            registers::OUT_X_L_A | 0x80,
-            6,
-        )?;
-
-        if data.len() < 6 {
-            bail!(ErrorKind::NotEnoughData);
+    pub fn read_acceleration(&mut self) -> Result<AccelerationVector, Dev::Error> {
+        self.read_acceleration_with_scale().map(|(accel, _)| accel)
+    }
+
+    /// Read the raw output of the accelerometer.
+    ///
+    /// Use `RawAcceleration::to_acceleration` to convert it,
+    /// or apply your own calibration.
+    ///
+    /// ```no_run
+    /// # use lsm303::Accelerometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// let raw = sensor.read_raw_acceleration()?;
+    /// println!("Counts: ({}, {}, {}) at {:?}",
+    ///     raw.counts.x, raw.counts.y, raw.counts.z, raw.scale);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_raw_acceleration(&mut self) -> Result<RawAcceleration, Dev::Error> {
+        // Setting the MSB of the register address enables auto-increment.
+        let mut data = [0; 6];
+        self.device
+            .write_read(I2C_ADDRESS, &[registers::OUT_X_L_A | 0x80], &mut data)
+            .map_err(|e| Error::read(SENSOR, registers::OUT_X_L_A, e))?;
+
+        Ok(self.raw_sample(&data))
+    }
+
+    /// Read the accelerometer, along with the scale the reading was taken at.
+    ///
+    /// If automatic full-scale ranging is enabled,
+    /// the scale may be adjusted after the reading.
+    /// The returned scale is always the one the reading was taken at.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, AutoScale};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_auto_scale(Some(AutoScale::default()));
+    /// let (accel, scale) = sensor.read_acceleration_with_scale()?;
+    /// println!("Acceleration: ({}, {}, {}) at {:?}",
+    ///     accel.x, accel.y, accel.z, scale);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_acceleration_with_scale(&mut self) -> Result<(AccelerationVector, Scale), Dev::Error> {
+        let raw = self.read_raw_acceleration()?;
+
+        if let Some(auto_scale) = self.auto_scale {
+            if let Some(scale) = self.next_scale(&raw, auto_scale) {
+                self.pending_scale = Some(scale);
+            }
         }
 
-        let mut cursor = Cursor::new(&data);
-
-        // The scale of the measurement, in g's.
-        // Refer to Table 3; linear acceleration sensitivity is measured in mg/LSB.
-        //
-        // `dimensioned` only defines the acceleration of free fall for
-        // UCUM, so we have to convert to SI.
-        let scale = MILLI * ucum::G_ *
-            match self.scale {
-                Scale::Scale2G => 1.0,
-                Scale::Scale4G => 2.0,
-                Scale::Scale8G => 4.0,
-                // This one doesn't follow the pattern - is the datasheet correct?
-                Scale::Scale16G => 12.0,
-            };
-        let scale: si::MeterPerSecond2<f64> = scale.into();
-
-        let x = (cursor.read_i16::<LittleEndian>()? >> 4) as f64 * scale;
-        let y = (cursor.read_i16::<LittleEndian>()? >> 4) as f64 * scale;
-        let z = (cursor.read_i16::<LittleEndian>()? >> 4) as f64 * scale;
-
-        let out = AccelerationVector { x, y, z };
-        Ok(out)
+        // The reading is valid at the scale it was taken at,
+        // so a failure to change the scale does not discard it.
+        if let Some(scale) = self.pending_scale {
+            if self.set_scale(scale).is_ok() {
+                self.pending_scale = None;
+            }
+        }
+
+        Ok((raw.to_acceleration(), raw.scale))
+    }
+
+    /// The scale that automatic full-scale ranging has chosen,
+    /// if it could not be set yet.
+    ///
+    /// Setting the scale fails if the bus does.
+    /// `read_acceleration_with_scale` still returns the reading in that case,
+    /// and tries again after the next reading.
+    pub fn pending_scale(&self) -> Option<Scale> {
+        self.pending_scale
+    }
+
+    /// Read the status register.
+    ///
+    /// ```no_run
+    /// # use lsm303::Accelerometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// if sensor.status()?.xyz_available {
+    ///     let accel = sensor.read_acceleration()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn status(&mut self) -> Result<Status, Dev::Error> {
+        use registers::{STATUS_REG_A, StatusRegA};
+        type R = StatusRegA;
+
+        let flags = read_register!(self.device, I2C_ADDRESS, SENSOR, STATUS_REG_A, StatusRegA)?;
+        let status = Status {
+            xyz_overrun: flags.contains(R::ZYXOR),
+            x_overrun: flags.contains(R::XOR),
+            y_overrun: flags.contains(R::YOR),
+            z_overrun: flags.contains(R::ZOR),
+            xyz_available: flags.contains(R::ZYXDA),
+            x_available: flags.contains(R::XDA),
+            y_available: flags.contains(R::YDA),
+            z_available: flags.contains(R::ZDA),
+        };
+        Ok(status)
+    }
+
+    /// Wait for new data on all axes, then read the accelerometer.
+    ///
+    /// The status that reported the new data is returned alongside it,
+    /// so that overruns can be detected.
+    /// Returns `Error::Timeout` if no new data arrives in time.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use lsm303::Accelerometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// let (accel, status) = sensor.read_acceleration_when_ready(Duration::from_millis(200))?;
+    /// if status.xyz_overrun {
+    ///     println!("Missed a sample");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_acceleration_when_ready(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<(AccelerationVector, Status), Dev::Error> {
+        use std::thread::sleep;
+        use std::time::Instant;
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            let status = self.status()?;
+            if status.xyz_available {
+                break status;
+            }
+            if Instant::now() > deadline {
+                return Err(Error::Timeout(SENSOR));
+            }
+            sleep(Duration::from_millis(1));
+        };
+
+        let accel = self.read_acceleration()?;
+        Ok((accel, status))
     }
 
     /// Set the scale of the acceleration measurement.
@@ -214,17 +925,17 @@ where
     /// ```no_run
     /// # use lsm303::accelerometer::{Accelerometer, Scale};
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
     /// sensor.set_scale(Scale::Scale4G)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_scale(&mut self, scale: Scale) -> Result<()> {
+    pub fn set_scale(&mut self, scale: Scale) -> Result<(), Dev::Error> {
         use registers::{CTRL_REG4_A, CtrlReg4A};
         type R = CtrlReg4A;
 
-        let mut flags = read_register!(self.device, CTRL_REG4_A, CtrlReg4A)?;
+        let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, CtrlReg4A)?;
         flags.remove(R::FS1 | R::FS0);
         let setting = match scale {
             Scale::Scale2G => R::empty(),
@@ -234,49 +945,771 @@ where
         };
         flags.insert(setting);
 
-        write_register!(self.device, CTRL_REG4_A, flags)?;
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, flags)?;
         self.scale = scale;
+        self.pending_scale = None;
 
         Ok(())
     }
 
+    /// The current scale of the acceleration measurement.
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    /// Enable or disable automatic full-scale ranging.
+    ///
+    /// Automatic ranging is disabled by default.
+    /// It only takes effect when reading the acceleration,
+    /// and not when reading the FIFO.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, AutoScale};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_auto_scale(Some(AutoScale {
+    ///     saturation: 0.9,
+    ///     fraction: 0.5,
+    ///     samples: 100,
+    /// }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_auto_scale(&mut self, auto_scale: Option<AutoScale>) {
+        self.auto_scale = auto_scale;
+        self.quiet_samples = 0;
+        self.pending_scale = None;
+    }
+
     /// Set the rate at which acceleration is measured.
     ///
+    /// Returns `Error::RateNotAvailableInMode` if the rate cannot be
+    /// used in the current power mode. `Rate1620Hz` and `Rate5376Hz` require
+    /// low-power mode, and `Rate1344Hz` requires normal mode.
+    ///
     /// ```no_run
     /// # use lsm303::accelerometer::{Accelerometer, Rate};
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
     /// sensor.set_rate(Rate::Rate100Hz)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_rate(&mut self, rate: Rate) -> Result<()> {
+    pub fn set_rate(&mut self, rate: Rate) -> Result<(), Dev::Error> {
         use registers::{CTRL_REG1_A, CtrlReg1A};
         type R = CtrlReg1A;
 
-        let mut flags = read_register!(self.device, CTRL_REG1_A, CtrlReg1A)?;
+        let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, CtrlReg1A)?;
+
+        if !rate_available(&rate, flags.contains(R::LPen)) {
+            return Err(Error::RateNotAvailableInMode);
+        }
+
         flags.remove(R::ODR3 | R::ODR2 | R::ODR1 | R::ODR0);
+        flags.insert(data_rate_flags(&rate));
+
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, flags)?;
+        self.rate = rate;
+
+        Ok(())
+    }
+
+    /// Set the power and resolution mode of the accelerometer.
+    ///
+    /// Returns `Error::RateNotAvailableInMode` if the current data rate
+    /// cannot be used in the new mode. In that case, change the rate first,
+    /// or change both with `set_rate_and_mode`.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, OperatingMode};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_mode(OperatingMode::Normal)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_mode(&mut self, mode: OperatingMode) -> Result<(), Dev::Error> {
+        use registers::{CTRL_REG1_A, CtrlReg1A, CTRL_REG4_A, CtrlReg4A};
+
+        let (low_power, high_resolution) = mode_flags(&mode);
+        if !rate_available(&self.rate, low_power) {
+            return Err(Error::RateNotAvailableInMode);
+        }
 
-        let setting = match rate {
-            _ => CtrlReg1A::empty(),
+        let mut ctrl_reg1_a = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, CtrlReg1A)?;
+        ctrl_reg1_a.set(CtrlReg1A::LPen, low_power);
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, ctrl_reg1_a)?;
+
+        let mut ctrl_reg4_a = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, CtrlReg4A)?;
+        ctrl_reg4_a.set(CtrlReg4A::HR, high_resolution);
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, ctrl_reg4_a)?;
+
+        self.mode = mode;
+
+        Ok(())
+    }
+
+    /// Set the data rate and the operating mode together.
+    ///
+    /// Only the new rate and mode need to be compatible, so this can move
+    /// between rates that `set_rate` and `set_mode` cannot reach one at a time,
+    /// such as from `Rate5376Hz` in low-power mode to `Rate1344Hz` in normal mode.
+    ///
+    /// Returns `Error::RateNotAvailableInMode` if the rate cannot be used in the mode.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, OperatingMode, Rate};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_rate_and_mode(Rate::Rate1620Hz, OperatingMode::LowPower)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_rate_and_mode(&mut self, rate: Rate, mode: OperatingMode) -> Result<(), Dev::Error> {
+        use registers::{CTRL_REG1_A, CtrlReg1A, CTRL_REG4_A, CtrlReg4A};
+        type R = CtrlReg1A;
+
+        let (low_power, high_resolution) = mode_flags(&mode);
+        if !rate_available(&rate, low_power) {
+            return Err(Error::RateNotAvailableInMode);
+        }
+
+        let mut ctrl_reg1_a = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, CtrlReg1A)?;
+        ctrl_reg1_a.remove(R::ODR3 | R::ODR2 | R::ODR1 | R::ODR0);
+        ctrl_reg1_a.insert(data_rate_flags(&rate));
+        ctrl_reg1_a.set(R::LPen, low_power);
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG1_A, ctrl_reg1_a)?;
+        self.rate = rate;
+
+        let mut ctrl_reg4_a = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, CtrlReg4A)?;
+        ctrl_reg4_a.set(CtrlReg4A::HR, high_resolution);
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG4_A, ctrl_reg4_a)?;
+
+        self.mode = mode;
+
+        Ok(())
+    }
+
+    /// Configure the FIFO buffer.
+    ///
+    /// The FIFO can store up to 32 samples.
+    /// In `Bypass` mode it is disabled entirely.
+    /// The watermark flag is raised once the number of stored samples
+    /// exceeds `watermark`, which must be less than 32.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, FifoMode, Rate};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_rate(Rate::Rate400Hz)?;
+    /// sensor.set_fifo_mode(FifoMode::Stream, 24)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_fifo_mode(&mut self, mode: FifoMode, watermark: u8) -> Result<(), Dev::Error> {
+        use registers::{CTRL_REG5_A, CtrlReg5A, FIFO_CTRL_REG_A, FifoCtrlRegA};
+        type R = FifoCtrlRegA;
+
+        if watermark > 31 {
+            return Err(Error::InvalidFifoWatermark);
+        }
+
+        let mut ctrl_reg5_a = read_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG5_A, CtrlReg5A)?;
+        ctrl_reg5_a.set(CtrlReg5A::FIFO_EN, !matches!(mode, FifoMode::Bypass));
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG5_A, ctrl_reg5_a)?;
+
+        let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, FIFO_CTRL_REG_A, FifoCtrlRegA)?;
+        flags.remove(R::FM1 | R::FM0 | R::FTH4 | R::FTH3 | R::FTH2 | R::FTH1 | R::FTH0);
+        let setting = match mode {
+            FifoMode::Bypass => R::empty(),
+            FifoMode::Fifo => R::FM0,
+            FifoMode::Stream => R::FM1,
+            FifoMode::StreamToFifo => R::FM1 | R::FM0,
         };
         flags.insert(setting);
+        // The watermark occupies the lower five bits.
+        flags.insert(R::from_bits_truncate(watermark));
 
-        write_register!(self.device, CTRL_REG1_A, flags)?;
-        self.rate = rate;
+        write_register!(self.device, I2C_ADDRESS, SENSOR, FIFO_CTRL_REG_A, flags)?;
+
+        Ok(())
+    }
+
+    /// Read all of the samples stored in the FIFO buffer.
+    ///
+    /// The samples are read in a single I2C transaction,
+    /// oldest first.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, FifoMode};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_fifo_mode(FifoMode::Stream, 16)?;
+    /// let fifo = sensor.read_fifo()?;
+    /// if fifo.overrun {
+    ///     println!("Some samples were lost");
+    /// }
+    /// for accel in fifo.samples() {
+    ///     println!("Acceleration: ({}, {}, {})",
+    ///         accel.x, accel.y, accel.z);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_fifo(&mut self) -> Result<FifoData, Dev::Error> {
+        use registers::{FIFO_SRC_REG_A, FifoSrcRegA};
+        type R = FifoSrcRegA;
+
+        let status = read_register!(self.device, I2C_ADDRESS, SENSOR, FIFO_SRC_REG_A, FifoSrcRegA)?;
+        let overrun = status.contains(R::OVRN_FIFO);
+        let watermark = status.contains(R::WTM);
+
+        // FSS holds the number of unread samples, but only has five bits.
+        // When the FIFO is full, the overrun flag is set instead.
+        let count = if status.contains(R::EMPTY) {
+            0
+        } else if overrun {
+            32
+        } else {
+            (status & (R::FSS4 | R::FSS3 | R::FSS2 | R::FSS1 | R::FSS0)).bits() as usize
+        };
+
+        let zero = 0.0 * si::MPS2;
+        let mut samples = [AccelerationVector {
+            x: zero,
+            y: zero,
+            z: zero,
+        }; 32];
+        if count > 0 {
+            // Read everything in one transaction. In FIFO mode the register
+            // address wraps around from `OUT_Z_H_A` back to `OUT_X_L_A`.
+            let mut buffer = [0; 32 * 6];
+            let data = &mut buffer[..count * 6];
+            self.device
+                .write_read(I2C_ADDRESS, &[registers::OUT_X_L_A | 0x80], data)
+                .map_err(|e| Error::read(SENSOR, registers::OUT_X_L_A, e))?;
+
+            for (sample, chunk) in samples.iter_mut().zip(data.chunks(6)) {
+                *sample = self.convert_sample(chunk);
+            }
+        }
+
+        let out = FifoData {
+            samples,
+            len: count,
+            overrun,
+            watermark,
+        };
+        Ok(out)
+    }
+
+    /// Configure one of the inertial interrupt generators.
+    ///
+    /// The threshold and duration are converted using the current
+    /// scale and data rate, so set those first.
+    /// Returns `Error::ThresholdOutOfRange` or
+    /// `Error::DurationOutOfRange` if they cannot be represented.
+    ///
+    /// This does not route the interrupt to a pin;
+    /// refer to `CTRL_REG3_A` and `CTRL_REG6_A` for that.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::units::si;
+    /// # use lsm303::accelerometer::{Accelerometer, Combination, Interrupt, InterruptConfig};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// // Detect free fall.
+    /// let config = InterruptConfig::new()
+    ///     .combination(Combination::And)
+    ///     .x_low(true)
+    ///     .y_low(true)
+    ///     .z_low(true)
+    ///     .threshold(3.0 * si::MPS2)
+    ///     .duration(0.05 * si::S);
+    /// sensor.configure_interrupt(Interrupt::Int2, config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure_interrupt(
+        &mut self,
+        interrupt: Interrupt,
+        config: InterruptConfig,
+    ) -> Result<(), Dev::Error> {
+        use registers::{INT1_CFG_A, INT1_DURATION_A, INT1_THS_A, INT2_CFG_A, INT2_DURATION_A,
+                        INT2_THS_A, IntCfgA, IntDurationA};
+        type R = IntCfgA;
+
+        let (cfg_register, ths_register, duration_register) = match interrupt {
+            Interrupt::Int1 => (INT1_CFG_A, INT1_THS_A, INT1_DURATION_A),
+            Interrupt::Int2 => (INT2_CFG_A, INT2_THS_A, INT2_DURATION_A),
+        };
+
+        // The threshold is compared against the 8 bit output,
+        // so each step is 16 times the 12 bit sensitivity.
+        let threshold = round(value(config.threshold / (16.0 * sensitivity(&self.scale))));
+        if !(0.0..=127.0).contains(&threshold) {
+            return Err(Error::ThresholdOutOfRange);
+        }
+        let threshold = threshold as u8;
+
+        // The duration is measured in samples.
+        let duration = round(value(config.duration * (frequency(&self.rate) * si::HZ)));
+        if !(0.0..=127.0).contains(&duration) {
+            return Err(Error::DurationOutOfRange);
+        }
+        let duration = IntDurationA::from_bits_truncate(duration as u8);
+
+        let mut flags = match config.combination {
+            Combination::Or => R::empty(),
+            Combination::And => R::AOI,
+        };
+        flags.set(R::_6D, config.six_direction);
+        flags.set(R::XHIE, config.x_high);
+        flags.set(R::XLIE, config.x_low);
+        flags.set(R::YHIE, config.y_high);
+        flags.set(R::YLIE, config.y_low);
+        flags.set(R::ZHIE, config.z_high);
+        flags.set(R::ZLIE, config.z_low);
+
+        self.device
+            .write(I2C_ADDRESS, &[ths_register, threshold])
+            .map_err(|e| Error::write(SENSOR, ths_register, e))?;
+        write_register!(self.device, I2C_ADDRESS, SENSOR, duration_register, duration)?;
+        write_register!(self.device, I2C_ADDRESS, SENSOR, cfg_register, flags)?;
+
+        Ok(())
+    }
+
+    /// Read which events caused an interrupt.
+    ///
+    /// If the interrupt is latched, reading its source clears it.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, Interrupt};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// let source = sensor.interrupt_source(Interrupt::Int1)?;
+    /// if source.active {
+    ///     println!("Motion detected");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_source(&mut self, interrupt: Interrupt) -> Result<InterruptSource, Dev::Error> {
+        use registers::{INT1_SOURCE_A, INT2_SOURCE_A, IntSrcA};
+        type R = IntSrcA;
+
+        let register = match interrupt {
+            Interrupt::Int1 => INT1_SOURCE_A,
+            Interrupt::Int2 => INT2_SOURCE_A,
+        };
+        let flags = read_register!(self.device, I2C_ADDRESS, SENSOR, register, IntSrcA)?;
+
+        let source = InterruptSource {
+            active: flags.contains(R::IA),
+            x_high: flags.contains(R::XH),
+            x_low: flags.contains(R::XL),
+            y_high: flags.contains(R::YH),
+            y_low: flags.contains(R::YL),
+            z_high: flags.contains(R::ZH),
+            z_low: flags.contains(R::ZL),
+        };
+        Ok(source)
+    }
+
+    /// Configure click and double-click detection.
+    ///
+    /// The threshold and time windows are converted using the current
+    /// scale and data rate, so set those first.
+    /// Returns `Error::ThresholdOutOfRange` or
+    /// `Error::DurationOutOfRange` if they cannot be represented.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # extern crate lsm303;
+    /// # use lsm303::units::si;
+    /// # use lsm303::accelerometer::{Accelerometer, ClickConfig, Rate};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.set_rate(Rate::Rate400Hz)?;
+    /// let config = ClickConfig::new()
+    ///     .x_single(true)
+    ///     .y_single(true)
+    ///     .threshold(8.0 * si::MPS2)
+    ///     .time_limit(Duration::from_millis(10));
+    /// sensor.configure_click(config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure_click(&mut self, config: ClickConfig) -> Result<(), Dev::Error> {
+        use registers::{CLICK_CFG_A, CLICK_THS_A, ClickCfgA, TIME_LATENCY_A, TIME_LIMIT_A,
+                        TIME_WINDOW_A};
+        type R = ClickCfgA;
+
+        // Like the interrupt generators, each step of the threshold
+        // is 16 times the 12 bit sensitivity.
+        let threshold = round(value(config.threshold / (16.0 * sensitivity(&self.scale))));
+        if !(0.0..=127.0).contains(&threshold) {
+            return Err(Error::ThresholdOutOfRange);
+        }
+
+        let time_limit = samples(config.time_limit, &self.rate);
+        let latency = samples(config.latency, &self.rate);
+        let window = samples(config.window, &self.rate);
+        if time_limit > 127.0 || latency > 255.0 || window > 255.0 {
+            return Err(Error::DurationOutOfRange);
+        }
+
+        let mut flags = R::empty();
+        flags.set(R::XS, config.x_single);
+        flags.set(R::XD, config.x_double);
+        flags.set(R::YS, config.y_single);
+        flags.set(R::YD, config.y_double);
+        flags.set(R::ZS, config.z_single);
+        flags.set(R::ZD, config.z_double);
+
+        let values = [
+            (CLICK_THS_A, threshold as u8),
+            (TIME_LIMIT_A, time_limit as u8),
+            (TIME_LATENCY_A, latency as u8),
+            (TIME_WINDOW_A, window as u8),
+        ];
+        for &(register, value) in &values {
+            self.device
+                .write(I2C_ADDRESS, &[register, value])
+                .map_err(|e| Error::write(SENSOR, register, e))?;
+        }
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CLICK_CFG_A, flags)?;
 
         Ok(())
     }
+
+    /// Read the most recent click, if any.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, ClickKind};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// if let Some(click) = sensor.read_click_event()? {
+    ///     match click.kind {
+    ///         ClickKind::Single => println!("Click"),
+    ///         ClickKind::Double => println!("Double click"),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_click_event(&mut self) -> Result<Option<ClickEvent>, Dev::Error> {
+        use registers::{CLICK_SRC_A, ClickSrcA};
+        type R = ClickSrcA;
+
+        let flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CLICK_SRC_A, ClickSrcA)?;
+        if !flags.contains(R::IA_click) {
+            return Ok(None);
+        }
+
+        let axis = if flags.contains(R::X) {
+            Axis::X
+        } else if flags.contains(R::Y) {
+            Axis::Y
+        } else if flags.contains(R::Z) {
+            Axis::Z
+        } else {
+            return Ok(None);
+        };
+        let kind = if flags.contains(R::DCLICK) {
+            ClickKind::Double
+        } else {
+            ClickKind::Single
+        };
+
+        let event = ClickEvent {
+            axis,
+            negative: flags.contains(R::Sign),
+            kind,
+        };
+        Ok(Some(event))
+    }
+
+    /// Configure the high-pass filter.
+    ///
+    /// ```no_run
+    /// # use lsm303::accelerometer::{Accelerometer, HighPassFilter, HighPassMode};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// let filter = HighPassFilter::new()
+    ///     .mode(HighPassMode::AutoReset)
+    ///     .interrupt1(true);
+    /// sensor.set_high_pass_filter(filter)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_high_pass_filter(&mut self, filter: HighPassFilter) -> Result<(), Dev::Error> {
+        use registers::{CTRL_REG2_A, CtrlReg2A};
+        type R = CtrlReg2A;
+
+        let mut flags = match filter.mode {
+            HighPassMode::NormalWithReset => R::empty(),
+            HighPassMode::Reference => R::HPM0,
+            HighPassMode::Normal => R::HPM1,
+            HighPassMode::AutoReset => R::HPM1 | R::HPM0,
+        };
+        flags.insert(match filter.cutoff {
+            HighPassCutoff::Highest => R::empty(),
+            HighPassCutoff::High => R::HPCF1,
+            HighPassCutoff::Low => R::HPCF2,
+            HighPassCutoff::Lowest => R::HPCF2 | R::HPCF1,
+        });
+        flags.set(R::FDS, filter.output);
+        flags.set(R::HPCLICK, filter.click);
+        flags.set(R::HPIS1, filter.interrupt1);
+        flags.set(R::HPIS2, filter.interrupt2);
+
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CTRL_REG2_A, flags)?;
+
+        Ok(())
+    }
+
+    /// Reset the high-pass filter by reading `REFERENCE_A`.
+    ///
+    /// This only has an effect in `HighPassMode::NormalWithReset`.
+    /// The value of the register is returned.
+    ///
+    /// ```no_run
+    /// # use lsm303::Accelerometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Accelerometer::new("/dev/i2c-1")?;
+    /// sensor.reset_high_pass_filter()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reset_high_pass_filter(&mut self) -> Result<u8, Dev::Error> {
+        let mut buffer = [0];
+        self.device
+            .write_read(I2C_ADDRESS, &[registers::REFERENCE_A], &mut buffer)
+            .map_err(|e| Error::read(SENSOR, registers::REFERENCE_A, e))?;
+        Ok(buffer[0])
+    }
+
+    /// Convert six bytes of output data, as laid out in
+    /// `OUT_X_L_A` through `OUT_Z_H_A`, into a vector of accelerations.
+    fn convert_sample(&self, data: &[u8]) -> AccelerationVector {
+        self.raw_sample(data).to_acceleration()
+    }
+
+    /// The scale that automatic ranging moves to after a reading, if any.
+    fn next_scale(&mut self, raw: &RawAcceleration, auto_scale: AutoScale) -> Option<Scale> {
+        // Compare the peak in g's, since the largest count is not the full
+        // scale at 16 g.
+        let lsb = value(lsb_size(raw.scale, raw.mode) / standard_gravity());
+        let counts = [raw.counts.x, raw.counts.y, raw.counts.z];
+        let peak = counts.iter().map(|&c| (c as f64).abs()).fold(0.0, f64::max) * lsb;
+
+        let larger = SCALES.iter().skip_while(|&&s| s != raw.scale).nth(1);
+        let smaller = SCALES.iter().take_while(|&&s| s != raw.scale).last();
+
+        if peak >= auto_scale.saturation * full_scale(raw.scale) {
+            self.quiet_samples = 0;
+            return larger.cloned();
+        }
+        if let Some(&smaller) = smaller {
+            if peak < auto_scale.fraction * full_scale(smaller) {
+                self.quiet_samples += 1;
+            } else {
+                self.quiet_samples = 0;
+            }
+            if self.quiet_samples >= auto_scale.samples {
+                self.quiet_samples = 0;
+                return Some(smaller);
+            }
+        }
+        None
+    }
+
+    /// Extract the raw counts from six bytes of output data.
+    fn raw_sample(&self, data: &[u8]) -> RawAcceleration {
+        use byteorder::{ByteOrder, LittleEndian};
+
+        // The output is left justified, with the number of significant
+        // bits depending on the operating mode.
+        let shift = match self.mode {
+            OperatingMode::HighResolution => 4,
+            OperatingMode::Normal => 6,
+            OperatingMode::LowPower => 8,
+        };
+
+        let x = LittleEndian::read_i16(&data[0..2]) >> shift;
+        let y = LittleEndian::read_i16(&data[2..4]) >> shift;
+        let z = LittleEndian::read_i16(&data[4..6]) >> shift;
+
+        RawAcceleration {
+            counts: Vector3 { x, y, z },
+            scale: self.scale,
+            mode: self.mode,
+        }
+    }
+}
+
+
+impl RawAcceleration {
+    /// Convert the raw counts into a vector of accelerations.
+    ///
+    /// ```
+    /// # extern crate lsm303;
+    /// # use lsm303::units::si;
+    /// # use lsm303::common::Vector3;
+    /// # use lsm303::accelerometer::{OperatingMode, RawAcceleration, Scale};
+    /// # fn main() {
+    /// let raw = RawAcceleration {
+    ///     counts: Vector3 { x: 0, y: 0, z: 1000 },
+    ///     scale: Scale::Scale2G,
+    ///     mode: OperatingMode::HighResolution,
+    /// };
+    /// let accel = raw.to_acceleration();
+    /// assert!((accel.z / si::MPS2 - 9.80665).abs() < 1e-9);
+    /// # }
+    /// ```
+    pub fn to_acceleration(&self) -> AccelerationVector {
+        let scale = lsb_size(self.scale, self.mode);
+
+        let x = self.counts.x as f64 * scale;
+        let y = self.counts.y as f64 * scale;
+        let z = self.counts.z as f64 * scale;
+
+        AccelerationVector { x, y, z }
+    }
+}
+
+
+/// The size of one count of raw output data, for a given scale and mode.
+///
+/// This is the sensitivity from Table 3 of the datasheet, which is
+/// given for 12 bit output. Each bit dropped in the lower resolution
+/// modes doubles the size of the LSB.
+pub fn lsb_size(scale: Scale, mode: OperatingMode) -> si::MeterPerSecond2<f64> {
+    let resolution = match mode {
+        OperatingMode::HighResolution => 1.0,
+        OperatingMode::Normal => 4.0,
+        OperatingMode::LowPower => 16.0,
+    };
+    resolution * sensitivity(&scale)
+}
+
+
+/// The size of the LSB of 12 bit output data.
+fn sensitivity(scale: &Scale) -> si::MeterPerSecond2<f64> {
+    // Refer to Table 3; linear acceleration sensitivity is measured in mg/LSB.
+    1e-3 * standard_gravity() *
+        match *scale {
+            Scale::Scale2G => 1.0,
+            Scale::Scale4G => 2.0,
+            Scale::Scale8G => 4.0,
+            // This one doesn't follow the pattern - is the datasheet correct?
+            Scale::Scale16G => 12.0,
+        }
+}
+
+
+/// The full scale of the measurement, in g's.
+fn full_scale(scale: Scale) -> f64 {
+    match scale {
+        Scale::Scale2G => 2.0,
+        Scale::Scale4G => 4.0,
+        Scale::Scale8G => 8.0,
+        Scale::Scale16G => 16.0,
+    }
+}
+
+
+/// The output data rate in Hz, or zero when powered down.
+fn frequency(rate: &Rate) -> f64 {
+    match *rate {
+        Rate::PowerDown => 0.0,
+        Rate::Rate1Hz => 1.0,
+        Rate::Rate10Hz => 10.0,
+        Rate::Rate25Hz => 25.0,
+        Rate::Rate50Hz => 50.0,
+        Rate::Rate100Hz => 100.0,
+        Rate::Rate200Hz => 200.0,
+        Rate::Rate400Hz => 400.0,
+        Rate::Rate1620Hz => 1620.0,
+        Rate::Rate1344Hz => 1344.0,
+        Rate::Rate5376Hz => 5376.0,
+    }
+}
+
+
+/// The number of samples that fit in a duration at the given data rate.
+fn samples(duration: Duration, rate: &Rate) -> f64 {
+    round(duration.as_secs_f64() * frequency(rate))
+}
+
+
+/// The output data rate bits of CTRL_REG1_A for a data rate.
+///
+/// Refer to Table 20 of the datasheet.
+fn data_rate_flags(rate: &Rate) -> registers::CtrlReg1A {
+    type R = registers::CtrlReg1A;
+
+    match *rate {
+        Rate::PowerDown => R::empty(),
+        Rate::Rate1Hz => R::ODR0,
+        Rate::Rate10Hz => R::ODR1,
+        Rate::Rate25Hz => R::ODR1 | R::ODR0,
+        Rate::Rate50Hz => R::ODR2,
+        Rate::Rate100Hz => R::ODR2 | R::ODR0,
+        Rate::Rate200Hz => R::ODR2 | R::ODR1,
+        Rate::Rate400Hz => R::ODR2 | R::ODR1 | R::ODR0,
+        Rate::Rate1620Hz => R::ODR3,
+        // These share a setting; the power mode determines the rate.
+        Rate::Rate1344Hz | Rate::Rate5376Hz => R::ODR3 | R::ODR0,
+    }
+}
+
+
+/// The LPen bit of CTRL_REG1_A and the HR bit of CTRL_REG4_A for an operating mode.
+///
+/// Refer to Table 20 and the description of CTRL_REG4_A in the datasheet.
+fn mode_flags(mode: &OperatingMode) -> (bool, bool) {
+    match *mode {
+        OperatingMode::LowPower => (true, false),
+        OperatingMode::Normal => (false, false),
+        OperatingMode::HighResolution => (false, true),
+    }
+}
+
+
+/// Whether a data rate can be used in normal or low-power mode.
+///
+/// Refer to Table 20 of the datasheet.
+fn rate_available(rate: &Rate, low_power: bool) -> bool {
+    match *rate {
+        Rate::Rate1620Hz | Rate::Rate5376Hz => low_power,
+        Rate::Rate1344Hz => !low_power,
+        _ => true,
+    }
 }
 
 
-/// Access the underlying `I2CDevice`.
+/// Access the underlying I2C bus.
 ///
 /// Most of the methods require a mutable reference; `DerefMut` is implemented as well.
 impl<Dev> Deref for Accelerometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     type Target = Dev;
 
@@ -286,12 +1719,12 @@ where
 }
 
 
-/// Access the underlying `I2CDevice`.
+/// Access the underlying I2C bus.
 ///
 /// Refer to the LSM303 datasheet if you plan on accessing the device directly.
 impl<Dev> DerefMut for Accelerometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     fn deref_mut(&mut self) -> &mut Dev {
         &mut self.device
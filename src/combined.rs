@@ -0,0 +1,335 @@
+//! Interface to both halves of the LSM303 over a single bus.
+//!
+//! The accelerometer and magnetometer are separate devices on the same
+//! I2C bus. `Lsm303` opens the bus once, shares it between an
+//! `Accelerometer` and a `Magnetometer`, and reads both together.
+//!
+//! This module is only available with the `std` feature.
+//!
+//! ```no_run
+//! # use lsm303::Lsm303;
+//! # use lsm303::combined::Config;
+//! # use lsm303::accelerometer::Scale;
+//! # fn main() { test().unwrap(); }
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut sensor = Lsm303::new("/dev/i2c-1")?;
+//! sensor.configure(Config::new().scale(Scale::Scale4G))?;
+//! let sample = sensor.read_all()?;
+//! println!("Acceleration: {:?}", sample.acceleration);
+//! # Ok(())
+//! # }
+//! ```
+
+use accelerometer::{self, AccelerationVector, Accelerometer, OperatingMode, Scale};
+use errors::Result;
+use embedded_hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+#[cfg(feature = "linux")]
+use errors::Error;
+#[cfg(feature = "linux")]
+use linux_embedded_hal::{I2CError, I2cdev};
+use magnetometer::{self, Gain, MagneticField, Magnetometer};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use units::si;
+
+
+/// An I2C bus shared by the accelerometer and magnetometer.
+///
+/// Each transfer locks the bus for its duration,
+/// so an `Lsm303` can be sent to another thread if the bus can.
+pub struct SharedBus<Dev> {
+    bus: Arc<Mutex<Dev>>,
+}
+
+
+/// Interface to an LSM303 accelerometer and magnetometer on one bus.
+pub struct Lsm303<Dev>
+where
+    Dev: I2c,
+{
+    bus: SharedBus<Dev>,
+    accelerometer: Accelerometer<SharedBus<Dev>>,
+    magnetometer: Magnetometer<SharedBus<Dev>>,
+}
+
+
+/// A reading of every sensor, taken together.
+#[derive(Debug)]
+pub struct Sample {
+    /// When the reading started.
+    pub timestamp: Instant,
+    /// The output of the accelerometer.
+    pub acceleration: AccelerationVector,
+    /// The output of the magnetometer.
+    pub magnetic_field: MagneticField,
+    /// The output of the temperature sensor.
+    pub temperature: si::Kelvin<f64>,
+}
+
+
+/// Settings for both sensors, applied by `Lsm303::configure`.
+///
+/// Settings that are not given are left unchanged.
+///
+/// ```
+/// # use lsm303::combined::Config;
+/// # use lsm303::accelerometer::{self, Scale};
+/// # use lsm303::magnetometer::{self, Gain};
+/// let config = Config::new()
+///     .accelerometer_rate(accelerometer::Rate::Rate100Hz)
+///     .scale(Scale::Scale8G)
+///     .magnetometer_rate(magnetometer::Rate::Rate75Hz)
+///     .gain(Gain::Gain_4_0);
+/// ```
+#[derive(Default)]
+pub struct Config {
+    accelerometer_rate: Option<accelerometer::Rate>,
+    scale: Option<Scale>,
+    mode: Option<OperatingMode>,
+    magnetometer_rate: Option<magnetometer::Rate>,
+    gain: Option<Gain>,
+}
+
+
+impl<Dev> SharedBus<Dev> {
+    fn new(device: Dev) -> SharedBus<Dev> {
+        SharedBus {
+            bus: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Lock the bus.
+    ///
+    /// A panic while the bus was locked does not stop either sensor
+    /// from using it afterwards.
+    fn lock(&self) -> MutexGuard<'_, Dev> {
+        self.bus.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+
+impl<Dev> Clone for SharedBus<Dev> {
+    fn clone(&self) -> SharedBus<Dev> {
+        SharedBus {
+            bus: Arc::clone(&self.bus),
+        }
+    }
+}
+
+
+impl<Dev> ErrorType for SharedBus<Dev>
+where
+    Dev: I2c,
+{
+    type Error = Dev::Error;
+}
+
+
+impl<Dev> I2c for SharedBus<Dev>
+where
+    Dev: I2c,
+{
+    fn read(&mut self, address: SevenBitAddress, read: &mut [u8]) -> ::core::result::Result<(), Dev::Error> {
+        self.lock().read(address, read)
+    }
+
+    fn write(&mut self, address: SevenBitAddress, write: &[u8]) -> ::core::result::Result<(), Dev::Error> {
+        self.lock().write(address, write)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> ::core::result::Result<(), Dev::Error> {
+        self.lock().write_read(address, write, read)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation],
+    ) -> ::core::result::Result<(), Dev::Error> {
+        self.lock().transaction(address, operations)
+    }
+}
+
+
+impl Config {
+    /// Leave every setting unchanged.
+    pub fn new() -> Config {
+        Config::default()
+    }
+
+    /// Set the data rate of the accelerometer.
+    pub fn accelerometer_rate(mut self, rate: accelerometer::Rate) -> Config {
+        self.accelerometer_rate = Some(rate);
+        self
+    }
+
+    /// Set the scale of the accelerometer.
+    pub fn scale(mut self, scale: Scale) -> Config {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Set the operating mode of the accelerometer.
+    pub fn mode(mut self, mode: OperatingMode) -> Config {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set the data rate of the magnetometer.
+    pub fn magnetometer_rate(mut self, rate: magnetometer::Rate) -> Config {
+        self.magnetometer_rate = Some(rate);
+        self
+    }
+
+    /// Set the gain of the magnetometer.
+    pub fn gain(mut self, gain: Gain) -> Config {
+        self.gain = Some(gain);
+        self
+    }
+}
+
+
+#[cfg(feature = "linux")]
+impl Lsm303<I2cdev> {
+    /// Initialize both sensors for a Linux I2C device.
+    ///
+    /// ```
+    /// # use lsm303::Lsm303;
+    /// let sensor = Lsm303::new("/dev/i2c-1");
+    /// ```
+    pub fn new<Path>(path: Path) -> Result<Lsm303<I2cdev>, I2CError>
+    where
+        Path: AsRef<::std::path::Path>,
+    {
+        let device = I2cdev::new(&path).map_err(|e| Error::FailedToOpenDevice(e.into()))?;
+
+        Lsm303::from_i2c_device(device)
+    }
+}
+
+
+impl<Dev> Lsm303<Dev>
+where
+    Dev: I2c,
+{
+    /// Initialize both sensors, given an open I2C device.
+    ///
+    /// The sensors are initialized the same way as
+    /// `Accelerometer::from_i2c_device` and `Magnetometer::from_i2c_device`.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::Lsm303;
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let device = I2cdev::new("/dev/i2c-1")?;
+    /// let sensor = Lsm303::from_i2c_device(device)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_i2c_device(device: Dev) -> Result<Lsm303<Dev>, Dev::Error> {
+        let bus = SharedBus::new(device);
+        let accelerometer = Accelerometer::from_i2c_device(bus.clone())?;
+        let magnetometer = Magnetometer::from_i2c_device(bus.clone())?;
+
+        Ok(Lsm303 {
+            bus,
+            accelerometer,
+            magnetometer,
+        })
+    }
+
+    /// Apply the settings in `config` to both sensors.
+    ///
+    /// If both the accelerometer rate and mode are given, they are
+    /// checked against each other rather than against the current settings.
+    ///
+    /// ```no_run
+    /// # use lsm303::Lsm303;
+    /// # use lsm303::combined::Config;
+    /// # use lsm303::magnetometer::Gain;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Lsm303::new("/dev/i2c-1")?;
+    /// sensor.configure(Config::new().gain(Gain::Gain_2_5))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure(&mut self, config: Config) -> Result<(), Dev::Error> {
+        match (config.accelerometer_rate, config.mode) {
+            (Some(rate), Some(mode)) => self.accelerometer.set_rate_and_mode(rate, mode)?,
+            (Some(rate), None) => self.accelerometer.set_rate(rate)?,
+            (None, Some(mode)) => self.accelerometer.set_mode(mode)?,
+            (None, None) => {}
+        }
+        if let Some(scale) = config.scale {
+            self.accelerometer.set_scale(scale)?;
+        }
+        if let Some(rate) = config.magnetometer_rate {
+            self.magnetometer.set_rate(rate)?;
+        }
+        if let Some(gain) = config.gain {
+            self.magnetometer.set_gain(gain)?;
+        }
+        Ok(())
+    }
+
+    /// Read the acceleration, magnetic field, and temperature.
+    ///
+    /// The timestamp is taken just before the accelerometer is read.
+    ///
+    /// The sample is all or nothing: if any read fails, the error is
+    /// returned and the other readings are discarded. In particular,
+    /// `Error::MagnetometerOverflow` on a strong field also loses the
+    /// acceleration. To keep it, read each sensor through `accelerometer`
+    /// and `magnetometer` instead.
+    ///
+    /// ```no_run
+    /// # use lsm303::Lsm303;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Lsm303::new("/dev/i2c-1")?;
+    /// let sample = sensor.read_all()?;
+    /// println!("Temperature: {}", sample.temperature);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_all(&mut self) -> Result<Sample, Dev::Error> {
+        let timestamp = Instant::now();
+        let acceleration = self.accelerometer.read_acceleration()?;
+        let magnetic_field = self.magnetometer.read_magnetic_field()?;
+        let temperature = self.magnetometer.read_temperature()?;
+
+        Ok(Sample {
+            timestamp,
+            acceleration,
+            magnetic_field,
+            temperature,
+        })
+    }
+
+    /// The accelerometer, for settings that are not covered by `Config`.
+    pub fn accelerometer(&mut self) -> &mut Accelerometer<SharedBus<Dev>> {
+        &mut self.accelerometer
+    }
+
+    /// The magnetometer, for settings that are not covered by `Config`.
+    pub fn magnetometer(&mut self) -> &mut Magnetometer<SharedBus<Dev>> {
+        &mut self.magnetometer
+    }
+
+    /// Access the underlying I2C bus.
+    ///
+    /// Refer to the LSM303 datasheet if you plan on accessing the device directly.
+    /// The bus stays locked until the guard is dropped.
+    pub fn bus(&self) -> MutexGuard<'_, Dev> {
+        self.bus.lock()
+    }
+}
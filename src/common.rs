@@ -2,8 +2,23 @@
 
 /// A simple three dimensional vector.
 #[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vector3<T> {
     pub x: T,
     pub y: T,
     pub z: T,
 }
+
+
+/// Round to the nearest integer, with ties away from zero.
+///
+/// `f64::round` is not available without `std`.
+pub(crate) fn round(value: f64) -> f64 {
+    if value.is_nan() {
+        value
+    } else if value < 0.0 {
+        (value - 0.5) as i64 as f64
+    } else {
+        (value + 0.5) as i64 as f64
+    }
+}
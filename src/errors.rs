@@ -1,26 +1,188 @@
-// This can probably be removed soon. See:
-// https://github.com/steveklabnik/rustdoc/issues/96
-#![allow(unused_doc_comment)]
-
 //! The error type for this crate.
+//!
+//! `Error` is generic over the error type of the I2C bus,
+//! and records which sensor and register a bus error occurred on.
+//! It does not allocate, and is the same with or without `std`.
+//!
+//! There is no error for a short read. An embedded-hal read either fills
+//! the whole buffer or fails with a bus error, so the driver never sees
+//! fewer bytes than it asked for.
+
+use core::fmt;
+
+
+/// The result type for this crate, given the error type of the I2C bus.
+pub type Result<T, E> = ::core::result::Result<T, Error<E>>;
+
+
+/// The half of the LSM303 that an error relates to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sensor {
+    /// The accelerometer.
+    Accelerometer,
+    /// The magnetometer.
+    Magnetometer,
+}
+
+
+/// A transfer over the I2C bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Reading from a register.
+    Read,
+    /// Writing to a register.
+    Write,
+}
+
+
+/// The axes on which the magnetometer overflowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    /// The X axis is out of range.
+    pub x: bool,
+    /// The Y axis is out of range.
+    pub y: bool,
+    /// The Z axis is out of range.
+    pub z: bool,
+}
+
+
+/// The errors that can occur.
+///
+/// `E` is the error type of the I2C bus.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Error opening the I2C device.
+    FailedToOpenDevice(E),
+
+    /// An error occurred reading or writing a register.
+    ///
+    /// For a multi-byte read, `register` is the first register read.
+    Bus {
+        /// The sensor being accessed.
+        sensor: Sensor,
+        /// Whether the register was being read or written.
+        operation: Operation,
+        /// The address of the register.
+        register: u8,
+        /// The error reported by the I2C bus.
+        error: E,
+    },
+
+    /// The requested data rate is not available in the current power mode.
+    RateNotAvailableInMode,
+
+    /// The requested scale is not available on this variant of the LSM303.
+    ScaleNotAvailable,
+
+    /// The FIFO watermark must be less than 32.
+    InvalidFifoWatermark,
+
+    /// An interrupt or click threshold cannot be represented at the current scale.
+    ThresholdOutOfRange,
+
+    /// An interrupt or click duration cannot be represented at the current data rate.
+    DurationOutOfRange,
 
-error_chain!{
-    errors {
-        /// Error opening the I2C device
-        FailedToOpenDevice{}
+    /// The sensor did not have new data in time.
+    Timeout(Sensor),
 
-        /// An insufficient amount of data was read from the device.
-        NotEnoughData{}
+    /// The identification registers did not match the expected device.
+    ///
+    /// Variants with a single `WHO_AM_I` register only use the first byte.
+    WrongDevice([u8; 3]),
 
-        /// An error occurred receiving information from the I2C slave.
-        FailedToReadRegister{}
+    /// The magnetic field exceeded the range of the gain on at least one axis.
+    MagnetometerOverflow(Overflow),
+}
+
+
+impl<E> Error<E> {
+    /// A bus error while reading `register`.
+    pub(crate) fn read(sensor: Sensor, register: u8, error: E) -> Error<E> {
+        Error::Bus {
+            sensor,
+            operation: Operation::Read,
+            register,
+            error,
+        }
+    }
 
-        /// An error occurred sending information to the I2C slave.
-        FailedToWriteRegister{}
+    /// A bus error while writing `register`.
+    pub(crate) fn write(sensor: Sensor, register: u8, error: E) -> Error<E> {
+        Error::Bus {
+            sensor,
+            operation: Operation::Write,
+            register,
+            error,
+        }
     }
+}
+
 
-    foreign_links {
-        I2C(::i2cdev::linux::LinuxI2CError) #[doc = "An error from an I2C device."];
-        IO(::std::io::Error) #[doc = "An IO error."];
+impl<E> From<Overflow> for Error<E> {
+    fn from(overflow: Overflow) -> Error<E> {
+        Error::MagnetometerOverflow(overflow)
     }
 }
+
+
+impl fmt::Display for Sensor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Sensor::Accelerometer => write!(f, "accelerometer"),
+            Sensor::Magnetometer => write!(f, "magnetometer"),
+        }
+    }
+}
+
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operation::Read => write!(f, "read"),
+            Operation::Write => write!(f, "write"),
+        }
+    }
+}
+
+
+impl<E> fmt::Display for Error<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::FailedToOpenDevice(ref error) => {
+                write!(f, "failed to open the I2C device: {:?}", error)
+            }
+            Error::Bus { sensor, operation, register, ref error } => write!(
+                f,
+                "failed to {} register 0x{:02X} of the {}: {:?}",
+                operation, register, sensor, error
+            ),
+            Error::RateNotAvailableInMode => {
+                write!(f, "data rate not available in the current power mode")
+            }
+            Error::ScaleNotAvailable => write!(f, "scale not available on this device"),
+            Error::InvalidFifoWatermark => write!(f, "FIFO watermark must be less than 32"),
+            Error::ThresholdOutOfRange => write!(f, "threshold out of range at the current scale"),
+            Error::DurationOutOfRange => write!(f, "duration out of range at the current data rate"),
+            Error::Timeout(sensor) => write!(f, "timed out waiting for the {}", sensor),
+            Error::WrongDevice(id) => {
+                write!(f, "unexpected identification registers: {:?}", id)
+            }
+            Error::MagnetometerOverflow(Overflow { x, y, z }) => {
+                write!(f, "magnetometer overflow (x: {}, y: {}, z: {})", x, y, z)
+            }
+        }
+    }
+}
+
+
+#[cfg(feature = "std")]
+impl<E> ::std::error::Error for Error<E>
+where
+    E: fmt::Debug,
+{
+}
@@ -2,22 +2,42 @@
 
 //! Interface to the LSM303 digital accelerometer and magnetometer.
 //!
+//! The driver is built on the [`embedded-hal`](https://docs.rs/embedded-hal)
+//! I2C traits, so it works with any platform that implements them.
+//! On Linux, the `linux` feature (enabled by default) provides
+//! `Lsm303::new`, `Accelerometer::new` and `Magnetometer::new`
+//! for opening a bus by path.
+//!
+//! The `std` feature is also enabled by default.
+//! Without it, the crate is `no_std` and does not allocate,
+//! and the methods that wait for new data, `Lsm303`,
+//! and the `recording` module are not available.
+//! Quantities are given in SI units, which are checked by
+//! the type system with the `dimensioned` feature (also enabled by default)
+//! and are plain `f64`s without it; see the `units` module.
+//!
+//! `Accelerometer` and `Magnetometer` support the LSM303DLHC.
+//! The LSM303D, LSM303AGR and LSM303C are supported by `variant::Driver`,
+//! which reads acceleration, magnetic field and temperature from any of them.
+//! `variant::detect` finds which of them are on a bus.
+//!
+//! The `mock` feature adds `mock::Device`, a simulated LSM303
+//! for testing code that uses the driver without hardware.
+//! The `simulator` feature adds `simulator::Simulator`, which also
+//! produces measurements from a scripted motion.
+//!
 //! - [Datasheet](http://www.st.com/resource/en/datasheet/lsm303dlhc.pdf)
 //! - [Application notes](http://www.st.com/content/ccc/resource/technical/document/application_note/e6/f0/fa/af/94/5e/43/de/CD00269797.pdf/files/CD00269797.pdf/jcr:content/translations/en.CD00269797.pdf)
 //!
 //! ```no_run
 //! # use std::time::Duration;
 //! # fn main() { test().unwrap(); }
-//! # fn test() -> lsm303::Result<()> {
-//! let device = "/dev/i2c-1";
-//! let mut accelerometer =
-//!     lsm303::Accelerometer::new(device)?;
-//! let mut magnetometer =
-//!     lsm303::Magnetometer::new(device)?;
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut sensor = lsm303::Lsm303::new("/dev/i2c-1")?;
 //!
 //!  loop {
-//!     let accel = accelerometer.read_acceleration()?;
-//!     let mag = magnetometer.read_magnetic_field()?;
+//!     let sample = sensor.read_all()?;
+//!     let (accel, mag) = (sample.acceleration, sample.magnetic_field);
 //!     println!("Accel: ({}, {}, {})  ||  Mag: ({}, {}, {})",
 //!              accel.x, accel.y, accel.z,
 //!              mag.x, mag.y, mag.z);
@@ -27,26 +47,34 @@
 //! # }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
 // External crates
 
+#[cfg(feature = "std")]
+extern crate core;
+
 #[macro_use]
 extern crate bitflags;
 
 extern crate byteorder;
 
+#[cfg(feature = "dimensioned")]
 extern crate dimensioned;
 
-#[macro_use]
-extern crate error_chain;
+extern crate embedded_hal;
 
-extern crate i2cdev;
+#[cfg(feature = "linux")]
+extern crate linux_embedded_hal;
 
 // Exports
 
 pub mod common;
 
-mod errors;
-pub use errors::{Error, ErrorKind, Result, ResultExt};
+pub mod units;
+
+pub mod errors;
+pub use errors::{Error, Result};
 
 #[macro_use]
 pub mod registers;
@@ -56,3 +84,19 @@ pub use accelerometer::Accelerometer;
 
 pub mod magnetometer;
 pub use magnetometer::Magnetometer;
+
+pub mod variant;
+
+#[cfg(feature = "std")]
+pub mod combined;
+#[cfg(feature = "std")]
+pub use combined::Lsm303;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+#[cfg(feature = "std")]
+pub mod recording;
+
+#[cfg(feature = "simulator")]
+pub mod simulator;
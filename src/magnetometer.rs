@@ -1,25 +1,42 @@
 //! Interface to the magnetometer.
 
 use common::Vector3;
-use dimensioned::{si, ucum};
-use errors::{Error, ErrorKind, Result, ResultExt};
-use i2cdev::core::I2CDevice;
-use i2cdev::linux::LinuxI2CDevice;
+use errors::{Error, Overflow, Result, Sensor};
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "linux")]
+use linux_embedded_hal::{I2CError, I2cdev};
 use registers;
-use std::ops::{Deref, DerefMut};
+use units::{gauss, si};
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use std::time::Duration;
 
 
 /// The I2C address of the magnetometer.
-const I2C_ADDRESS: u16 = 0x3C >> 1;
+const I2C_ADDRESS: u8 = 0x3C >> 1;
+
+
+/// The sensor reported in errors.
+const SENSOR: Sensor = Sensor::Magnetometer;
+
+
+/// The value of an output register when the measurement is out of range.
+pub(crate) const OVERFLOW: i16 = -4096;
 
 
 /// Interface to an LSM303 digital magnetometer.
 pub struct Magnetometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     device: Dev,
     gain: Gain,
+    rate: Rate,
+    mode: Mode,
+    temperature_offset: si::Kelvin<f64>,
+    auto_gain: Option<AutoGain>,
+    quiet_samples: u32,
+    pending_gain: Option<Gain>,
 }
 
 
@@ -29,6 +46,7 @@ pub type MagneticField = Vector3<si::Tesla<f64>>;
 
 /// The allowed settings for the gain on the magnetometer.
 #[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Gain {
     /// +/- 1.3 Gauss
     Gain_1_3,
@@ -47,20 +65,134 @@ pub enum Gain {
 }
 
 
-impl Magnetometer<LinuxI2CDevice> {
+/// All of the gains, from the smallest range to the largest.
+const GAINS: [Gain; 7] = [
+    Gain::Gain_1_3,
+    Gain::Gain_1_9,
+    Gain::Gain_2_5,
+    Gain::Gain_4_0,
+    Gain::Gain_4_7,
+    Gain::Gain_5_6,
+    Gain::Gain_8_1,
+];
+
+
+/// Settings for automatic gain ranging.
+///
+/// When any axis overflows, the gain is stepped up to the next larger range.
+/// When every axis stays below `fraction` of the full scale of the
+/// next smaller range for `samples` consecutive readings,
+/// the gain is stepped down. Keeping `fraction` below one provides hysteresis.
+///
+/// ```no_run
+/// # use lsm303::magnetometer::{AutoGain, Magnetometer};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut mag = Magnetometer::new("/dev/i2c-1")?;
+/// mag.set_auto_gain(Some(AutoGain::default()));
+/// let (field, gain) = mag.read_magnetic_field_with_gain()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct AutoGain {
+    /// The fraction of the smaller full scale under which readings must stay.
+    pub fraction: f64,
+    /// The number of consecutive readings before stepping down.
+    pub samples: u32,
+}
+
+
+impl Default for AutoGain {
+    fn default() -> AutoGain {
+        AutoGain {
+            fraction: 0.75,
+            samples: 10,
+        }
+    }
+}
+
+
+/// Raw output of the magnetometer.
+pub struct RawMagneticField {
+    /// The output on each axis.
+    pub counts: Vector3<i16>,
+    /// The gain that was active when the counts were read.
+    pub gain: Gain,
+}
+
+
+/// Available values for the data rate.
+///
+/// See Table 72 of the LSM303 datasheet.
+/// The default rate is `15 Hz`.
+///
+/// ```no_run
+/// # use lsm303::magnetometer::{Magnetometer, Rate};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut mag = Magnetometer::new("/dev/i2c-1")?;
+/// mag.set_rate(Rate::Rate75Hz)?;
+/// # Ok(())
+/// # }
+/// ```
+#[allow(non_camel_case_types)]
+pub enum Rate {
+    /// 0.75 Hz
+    Rate0_75Hz,
+    /// 1.5 Hz
+    Rate1_5Hz,
+    /// 3.0 Hz
+    Rate3Hz,
+    /// 7.5 Hz
+    Rate7_5Hz,
+    /// 15 Hz
+    Rate15Hz,
+    /// 30 Hz
+    Rate30Hz,
+    /// 75 Hz
+    Rate75Hz,
+    /// 220 Hz
+    Rate220Hz,
+}
+
+
+/// Operating modes of the magnetometer.
+///
+/// See Table 78 of the LSM303 datasheet.
+/// The default mode is `Continuous`.
+pub enum Mode {
+    /// Measurements are taken continuously at the configured data rate.
+    Continuous,
+    /// A single measurement is taken, after which the device goes to sleep.
+    Single,
+    /// The device is idle.
+    Sleep,
+}
+
+
+/// The decoded contents of `SR_REG_M`.
+pub struct Status {
+    /// New data is available in the output registers.
+    pub ready: bool,
+    /// The output registers are locked while they are being read.
+    pub lock: bool,
+}
+
+
+#[cfg(feature = "linux")]
+impl Magnetometer<I2cdev> {
     /// Initialize the magnetometer for a Linux I2C device.
     ///
     /// ```
     /// # use lsm303::Magnetometer;
     /// let sensor = Magnetometer::new("/dev/i2c-1");
     /// ```
-    pub fn new<Path>(path: Path) -> Result<Magnetometer<LinuxI2CDevice>>
+    pub fn new<Path>(path: Path) -> Result<Magnetometer<I2cdev>, I2CError>
     where
         Path: AsRef<::std::path::Path>,
     {
-        let device = LinuxI2CDevice::new(&path, I2C_ADDRESS).chain_err(|| {
-            ErrorKind::FailedToOpenDevice
-        })?;
+        let device = I2cdev::new(&path).map_err(|e| Error::FailedToOpenDevice(e.into()))?;
 
         Magnetometer::from_i2c_device(device)
     }
@@ -69,43 +201,86 @@ impl Magnetometer<LinuxI2CDevice> {
 
 impl<Dev> Magnetometer<Dev>
 where
-    Dev: I2CDevice,
-    Error: From<Dev::Error>,
-    Dev::Error: Send + 'static,
+    Dev: I2c,
 {
     /// Initialize the magnetometer, given an open I2C device.
     ///
     /// The opening of the device is platform specific,
     /// but initialization of the sensor is not.
-    /// Prefer to use `Accelerometer::new`, unless you are using an
-    /// implementation of `I2CDevice` that is not covered by this crate.
+    /// Prefer to use `Magnetometer::new`, unless you are using an
+    /// implementation of `I2c` that is not covered by this crate.
+    ///
+    /// The identification registers are checked first, and
+    /// `Error::WrongDevice` is returned if they do not match.
     ///
     /// ```no_run
     /// # extern crate lsm303;
     /// # use lsm303::Magnetometer;
-    /// # extern crate i2cdev;
-    /// # use i2cdev::linux::LinuxI2CDevice;
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
-    /// let device = LinuxI2CDevice::new("/dev/i2c-1", 0x3C >> 1)?;
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let device = I2cdev::new("/dev/i2c-1")?;
     /// let sensor = Magnetometer::from_i2c_device(device)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn from_i2c_device(mut device: Dev) -> Result<Magnetometer<Dev>> {
+    pub fn from_i2c_device(mut device: Dev) -> Result<Magnetometer<Dev>, Dev::Error> {
+        // Refer to Table 81 - 83 of the datasheet.
+        let mut id = [0; 3];
+        device
+            .write_read(I2C_ADDRESS, &[registers::IRA_REG_M], &mut id)
+            .map_err(|e| Error::read(SENSOR, registers::IRA_REG_M, e))?;
+        if &id != b"H43" {
+            return Err(Error::WrongDevice(id));
+        }
+
+        Magnetometer::from_i2c_device_unchecked(device)
+    }
+
+
+    /// Initialize the magnetometer, without checking the identification registers.
+    ///
+    /// This is useful for compatible devices that identify themselves differently.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::Magnetometer;
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let device = I2cdev::new("/dev/i2c-1")?;
+    /// let sensor = Magnetometer::from_i2c_device_unchecked(device)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_i2c_device_unchecked(mut device: Dev) -> Result<Magnetometer<Dev>, Dev::Error> {
         use registers::{CRA_REG_M, MR_REG_M, CraRegM, MrRegM};
 
         // Set magnetometer to continuous mode
         let mr_reg_m = MrRegM::empty();
-        write_register!(device, MR_REG_M, mr_reg_m)?;
+        write_register!(device, I2C_ADDRESS, SENSOR, MR_REG_M, mr_reg_m)?;
 
         // enable temperature; set output rate to 15 Hz
         let cra_reg_m = CraRegM::TEMP_EN | CraRegM::DO2;
-        write_register!(device, CRA_REG_M, cra_reg_m)?;
+        write_register!(device, I2C_ADDRESS, SENSOR, CRA_REG_M, cra_reg_m)?;
 
         let gain = Gain::Gain_1_3;
-
-        let mut magnetometer = Magnetometer { device, gain };
+        let rate = Rate::Rate15Hz;
+        let mode = Mode::Continuous;
+        let temperature_offset = 293.15 * si::K;
+
+        let mut magnetometer = Magnetometer {
+            device,
+            gain,
+            rate,
+            mode,
+            temperature_offset,
+            auto_gain: None,
+            quiet_samples: 0,
+            pending_gain: None,
+        };
         magnetometer.set_gain(Gain::Gain_1_3)?;
 
         Ok(magnetometer)
@@ -114,10 +289,13 @@ where
 
     /// Read the magnetometer, returning the magnetic field as a vector.
     ///
+    /// Returns `Error::MagnetometerOverflow` if the field on any axis
+    /// exceeds the range of the current gain.
+    ///
     /// ```no_run
     /// # use lsm303::Magnetometer;
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
     /// let field = sensor.read_magnetic_field()?;
     /// println!("Magnetic field: ({}, {}, {})",
@@ -125,37 +303,153 @@ where
     /// # Ok(())
     /// # }
     /// ```
-    pub fn read_magnetic_field(&mut self) -> Result<MagneticField> {
-        use byteorder::{ByteOrder, BigEndian};
+    pub fn read_magnetic_field(&mut self) -> Result<MagneticField, Dev::Error> {
+        self.read_magnetic_field_with_gain().map(|(field, _)| field)
+    }
+
+
+    /// Read the magnetometer, along with the gain the reading was taken at.
+    ///
+    /// If automatic gain ranging is enabled, the gain may be adjusted
+    /// after the reading. An overflow still returns an error,
+    /// but subsequent readings will use a larger range.
+    /// The returned gain is always the one the reading was taken at.
+    ///
+    /// ```no_run
+    /// # use lsm303::magnetometer::{AutoGain, Magnetometer};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_auto_gain(Some(AutoGain::default()));
+    /// let (field, gain) = sensor.read_magnetic_field_with_gain()?;
+    /// println!("Magnetic field: ({}, {}, {}) at {:?}",
+    ///     field.x, field.y, field.z, gain);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_magnetic_field_with_gain(&mut self) -> Result<(MagneticField, Gain), Dev::Error> {
+        let raw = self.read_raw_magnetic_field()?;
 
-        let data = self.device.smbus_read_i2c_block_data(
-            registers::OUT_X_H_M,
-            6,
-        )?;
-        if data.len() < 6 {
-            bail!(ErrorKind::NotEnoughData);
+        if let Some(auto_gain) = self.auto_gain {
+            if let Some(gain) = self.next_gain(&raw, auto_gain) {
+                self.pending_gain = Some(gain);
+            }
         }
 
-        // Refer to Table 3 or Table 75 of the datasheet.
-        let (scale_xy, scale_z) = match self.gain {
-            Gain::Gain_1_3 => (1100., 980.),
-            Gain::Gain_1_9 => (855.0, 760.),
-            Gain::Gain_2_5 => (670., 600.),
-            Gain::Gain_4_0 => (450., 400.),
-            Gain::Gain_4_7 => (400., 355.),
-            Gain::Gain_5_6 => (330., 295.),
-            Gain::Gain_8_1 => (230., 205.),
-        };
-        let scale_xy: si::Tesla<f64> = (ucum::GS / scale_xy).into();
-        let scale_z: si::Tesla<f64> = (ucum::GS / scale_z).into();
+        // The reading is valid at the gain it was taken at,
+        // so a failure to change the gain does not discard it.
+        if let Some(gain) = self.pending_gain {
+            if self.set_gain(gain).is_ok() {
+                self.pending_gain = None;
+            }
+        }
+
+        let out = raw.to_magnetic_field()?;
+        Ok((out, raw.gain))
+    }
+
+    /// The gain that automatic gain ranging has chosen,
+    /// if it could not be set yet.
+    ///
+    /// Setting the gain fails if the bus does.
+    /// `read_magnetic_field_with_gain` still returns the reading in that case,
+    /// and tries again after the next reading.
+    pub fn pending_gain(&self) -> Option<Gain> {
+        self.pending_gain
+    }
+
+
+    /// Read the raw output of the magnetometer.
+    ///
+    /// Use `RawMagneticField::to_magnetic_field` to convert it,
+    /// or apply your own calibration.
+    /// An axis that is out of range reads as -4096.
+    ///
+    /// ```no_run
+    /// # use lsm303::Magnetometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// let raw = sensor.read_raw_magnetic_field()?;
+    /// println!("Counts: ({}, {}, {}) at {:?}",
+    ///     raw.counts.x, raw.counts.y, raw.counts.z, raw.gain);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_raw_magnetic_field(&mut self) -> Result<RawMagneticField, Dev::Error> {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let mut data = [0; 6];
+        self.device
+            .write_read(I2C_ADDRESS, &[registers::OUT_X_H_M], &mut data)
+            .map_err(|e| Error::read(SENSOR, registers::OUT_X_H_M, e))?;
 
         // Yes indeed, the registers are ordered as X, Z, Y
-        let x = BigEndian::read_i16(&data[0..2]) as f64 * scale_xy;
-        let z = BigEndian::read_i16(&data[2..4]) as f64 * scale_z;
-        let y = BigEndian::read_i16(&data[4..6]) as f64 * scale_xy;
+        let x = BigEndian::read_i16(&data[0..2]);
+        let z = BigEndian::read_i16(&data[2..4]);
+        let y = BigEndian::read_i16(&data[4..6]);
 
-        let out = MagneticField { x, y, z };
-        Ok(out)
+        let raw = RawMagneticField {
+            counts: Vector3 { x, y, z },
+            gain: self.gain,
+        };
+        Ok(raw)
+    }
+
+
+    /// Read the status register.
+    ///
+    /// ```no_run
+    /// # use lsm303::Magnetometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// if sensor.status()?.ready {
+    ///     let field = sensor.read_magnetic_field()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn status(&mut self) -> Result<Status, Dev::Error> {
+        use registers::{SR_REG_M, SrRegM};
+
+        let flags = read_register!(self.device, I2C_ADDRESS, SENSOR, SR_REG_M, SrRegM)?;
+        let status = Status {
+            ready: flags.contains(SrRegM::DRDY),
+            lock: flags.contains(SrRegM::LOCK),
+        };
+        Ok(status)
+    }
+
+
+    /// Wait for new data, then read the magnetometer.
+    ///
+    /// Returns `Error::Timeout` if no new data arrives in time.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use lsm303::Magnetometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// let field = sensor.read_magnetic_field_when_ready(Duration::from_millis(100))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_magnetic_field_when_ready(&mut self, timeout: Duration) -> Result<MagneticField, Dev::Error> {
+        use std::thread::sleep;
+        use std::time::Instant;
+
+        let deadline = Instant::now() + timeout;
+        while !self.status()?.ready {
+            if Instant::now() > deadline {
+                return Err(Error::Timeout(SENSOR));
+            }
+            sleep(Duration::from_millis(1));
+        }
+
+        self.read_magnetic_field()
     }
 
 
@@ -164,19 +458,16 @@ where
     /// ```no_run
     /// # use lsm303::magnetometer::{Magnetometer, Gain};
     /// # fn main() { test().unwrap(); }
-    /// # fn test() -> lsm303::Result<()> {
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
     /// sensor.set_gain(Gain::Gain_4_0)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_gain(&mut self, gain: Gain) -> Result<()>
-    where
-        Dev::Error: Send + 'static,
-    {
+    pub fn set_gain(&mut self, gain: Gain) -> Result<(), Dev::Error> {
         use registers::{CRB_REG_M, CrbRegM};
         type R = CrbRegM;
-        let mut flags = read_register!(self.device, CRB_REG_M, CrbRegM)?;
+        let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CRB_REG_M, CrbRegM)?;
 
         flags.remove(R::GN2 | R::GN1 | R::GN0);
         let setting = match gain {
@@ -190,42 +481,323 @@ where
         };
         flags.insert(setting);
 
-        write_register!(self.device, CRB_REG_M, flags)?;
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CRB_REG_M, flags)?;
         self.gain = gain;
+        self.pending_gain = None;
+
+        Ok(())
+    }
+
+
+    /// The current gain of the magnetometer.
+    pub fn gain(&self) -> Gain {
+        self.gain
+    }
+
+
+    /// Enable or disable automatic gain ranging.
+    ///
+    /// Automatic gain ranging is disabled by default.
+    /// It only takes effect when reading the magnetic field.
+    ///
+    /// ```no_run
+    /// # use lsm303::magnetometer::{AutoGain, Magnetometer};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_auto_gain(Some(AutoGain {
+    ///     fraction: 0.5,
+    ///     samples: 50,
+    /// }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_auto_gain(&mut self, auto_gain: Option<AutoGain>) {
+        self.auto_gain = auto_gain;
+        self.quiet_samples = 0;
+        self.pending_gain = None;
+    }
+
+
+    /// Set the rate at which the magnetic field is measured.
+    ///
+    /// ```no_run
+    /// # use lsm303::magnetometer::{Magnetometer, Rate};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_rate(Rate::Rate30Hz)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_rate(&mut self, rate: Rate) -> Result<(), Dev::Error> {
+        use registers::{CRA_REG_M, CraRegM};
+        type R = CraRegM;
+        let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CRA_REG_M, CraRegM)?;
+
+        flags.remove(R::DO2 | R::DO1 | R::DO0);
+        let setting = match rate {
+            Rate::Rate0_75Hz => R::empty(),
+            Rate::Rate1_5Hz => R::DO0,
+            Rate::Rate3Hz => R::DO1,
+            Rate::Rate7_5Hz => R::DO1 | R::DO0,
+            Rate::Rate15Hz => R::DO2,
+            Rate::Rate30Hz => R::DO2 | R::DO0,
+            Rate::Rate75Hz => R::DO2 | R::DO1,
+            Rate::Rate220Hz => R::DO2 | R::DO1 | R::DO0,
+        };
+        flags.insert(setting);
+
+        write_register!(self.device, I2C_ADDRESS, SENSOR, CRA_REG_M, flags)?;
+        self.rate = rate;
 
         Ok(())
     }
 
 
-    // It is unclear how to interpret the TEMP_OUT registers.
-    // The datasheet does not have quite enough information.
-    // Discussions can be found in various places, such as
-    // https://forum.pololu.com/t/16-bit-values-in-lsm303/8499/8
-    // Until this is figured out, this function is being left out.
-    #[cfg(none)]
+    /// Set the operating mode of the magnetometer.
+    ///
+    /// ```no_run
+    /// # use lsm303::magnetometer::{Magnetometer, Mode};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_mode(Mode::Sleep)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_mode(&mut self, mode: Mode) -> Result<(), Dev::Error> {
+        use registers::{MR_REG_M, MrRegM};
+        type R = MrRegM;
+
+        let flags = match mode {
+            Mode::Continuous => R::empty(),
+            Mode::Single => R::MD0,
+            Mode::Sleep => R::MD1 | R::MD0,
+        };
+
+        write_register!(self.device, I2C_ADDRESS, SENSOR, MR_REG_M, flags)?;
+        self.mode = mode;
+
+        Ok(())
+    }
+
+
+    /// Take a single measurement, and leave the magnetometer asleep.
+    ///
+    /// This triggers a single conversion, waits for the data to be ready,
+    /// and returns the magnetic field. Data from an earlier measurement that
+    /// has not been read yet is discarded first, so that it is not mistaken
+    /// for the new one.
+    /// Returns `Error::Timeout` if no data arrives within 100 ms.
+    ///
+    /// ```no_run
+    /// # use lsm303::magnetometer::{Magnetometer, Mode};
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_mode(Mode::Sleep)?;
+    /// let field = sensor.measure_once()?;
+    /// println!("Magnetic field: ({}, {}, {})",
+    ///     field.x, field.y, field.z);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn measure_once(&mut self) -> Result<MagneticField, Dev::Error> {
+        // Reading the output clears the data-ready bit.
+        if self.status()?.ready {
+            self.read_raw_magnetic_field()?;
+        }
+        self.set_mode(Mode::Single)?;
+
+        let field = self.read_magnetic_field_when_ready(Duration::from_millis(100))?;
+
+        // The device goes to sleep on its own after a single conversion.
+        self.mode = Mode::Sleep;
+
+        Ok(field)
+    }
+
+
     /// Read the thermometer.
-    pub fn read_temperature(&mut self) -> Result<i16> {
-
-        let data = self.device.smbus_read_i2c_block_data(
-            registers::TEMP_OUT_H_M,
-            2,
-        )?;
-        if data.len() < 2 {
-            bail!(ErrorKind::NotEnoughData);
+    ///
+    /// The output has 12 bits, with a sensitivity of 8 LSB/°C.
+    /// The datasheet does not document the zero point,
+    /// so the result is only as accurate as the offset set with
+    /// `set_temperature_offset`. Discussions can be found in
+    /// various places, such as
+    /// https://forum.pololu.com/t/16-bit-values-in-lsm303/8499/8
+    ///
+    /// ```no_run
+    /// # use lsm303::Magnetometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// let temperature = sensor.read_temperature()?;
+    /// println!("Temperature: {}", temperature);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_temperature(&mut self) -> Result<si::Kelvin<f64>, Dev::Error> {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let mut data = [0; 2];
+        self.device
+            .write_read(I2C_ADDRESS, &[registers::TEMP_OUT_H_M], &mut data)
+            .map_err(|e| Error::read(SENSOR, registers::TEMP_OUT_H_M, e))?;
+
+        // The value is left justified; shift to keep the sign.
+        let raw = BigEndian::read_i16(&data[0..2]) >> 4;
+
+        let temperature = raw as f64 / 8.0 * si::K + self.temperature_offset;
+        Ok(temperature)
+    }
+
+
+    /// Set the temperature that corresponds to a raw reading of zero.
+    ///
+    /// The default is 20 °C, which is a commonly reported value.
+    /// Calibrate it against a known temperature for better accuracy.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::units::si;
+    /// # use lsm303::Magnetometer;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut sensor = Magnetometer::new("/dev/i2c-1")?;
+    /// sensor.set_temperature_offset(296.0 * si::K);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_temperature_offset(&mut self, offset: si::Kelvin<f64>) {
+        self.temperature_offset = offset;
+    }
+
+
+    /// The gain that automatic ranging moves to after a reading, if any.
+    fn next_gain(&mut self, raw: &RawMagneticField, auto_gain: AutoGain) -> Option<Gain> {
+        if raw.overflow() {
+            self.quiet_samples = 0;
+            return GAINS.iter().skip_while(|&&g| g != raw.gain).nth(1).cloned();
         }
 
-        let temp = (data[0] as i16) << 4 | data[1] as i16 >> 4;
-        Ok(temp)
+        let smaller = *GAINS.iter().take_while(|&&g| g != raw.gain).last()?;
+        let (scale_xy, scale_z) = sensitivity(raw.gain);
+        let Vector3 { x, y, z } = raw.counts;
+        let limit = auto_gain.fraction * full_scale(smaller);
+        let quiet = (x as f64 / scale_xy).abs() < limit &&
+            (y as f64 / scale_xy).abs() < limit &&
+            (z as f64 / scale_z).abs() < limit;
+        if quiet {
+            self.quiet_samples += 1;
+        } else {
+            self.quiet_samples = 0;
+        }
+        if self.quiet_samples >= auto_gain.samples {
+            self.quiet_samples = 0;
+            return Some(smaller);
+        }
+        None
+    }
+}
+
+
+impl RawMagneticField {
+    /// Whether any axis is out of range of the gain.
+    pub fn overflow(&self) -> bool {
+        self.counts.x == OVERFLOW || self.counts.y == OVERFLOW || self.counts.z == OVERFLOW
+    }
+
+    /// Convert the raw counts into a magnetic field.
+    ///
+    /// Returns the overflowing axes if any axis is out of range.
+    ///
+    /// ```
+    /// # extern crate lsm303;
+    /// # use lsm303::units::si;
+    /// # use lsm303::common::Vector3;
+    /// # use lsm303::magnetometer::{Gain, RawMagneticField};
+    /// # fn main() {
+    /// let raw = RawMagneticField {
+    ///     counts: Vector3 { x: 1100, y: 0, z: 980 },
+    ///     gain: Gain::Gain_1_3,
+    /// };
+    /// let field = raw.to_magnetic_field().unwrap();
+    /// assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    /// assert!((field.z / si::T - 1e-4).abs() < 1e-12);
+    /// # }
+    /// ```
+    pub fn to_magnetic_field(&self) -> ::core::result::Result<MagneticField, Overflow> {
+        let Vector3 { x, y, z } = self.counts;
+
+        // An axis reads as -4096 when the field exceeds the range of the gain.
+        if self.overflow() {
+            return Err(Overflow {
+                x: x == OVERFLOW,
+                y: y == OVERFLOW,
+                z: z == OVERFLOW,
+            });
+        }
+
+        let (scale_xy, scale_z) = lsb_size(self.gain);
+
+        let x = x as f64 * scale_xy;
+        let y = y as f64 * scale_xy;
+        let z = z as f64 * scale_z;
+
+        let out = MagneticField { x, y, z };
+        Ok(out)
+    }
+}
+
+
+/// The size of one count of raw output on the X/Y and Z axes, for a given gain.
+///
+/// Refer to Table 3 or Table 75 of the datasheet.
+pub fn lsb_size(gain: Gain) -> (si::Tesla<f64>, si::Tesla<f64>) {
+    let (scale_xy, scale_z) = sensitivity(gain);
+    (gauss() / scale_xy, gauss() / scale_z)
+}
+
+
+/// The sensitivity of the X/Y and Z axes, in LSB/Gauss.
+///
+/// Refer to Table 3 or Table 75 of the datasheet.
+fn sensitivity(gain: Gain) -> (f64, f64) {
+    match gain {
+        Gain::Gain_1_3 => (1100., 980.),
+        Gain::Gain_1_9 => (855.0, 760.),
+        Gain::Gain_2_5 => (670., 600.),
+        Gain::Gain_4_0 => (450., 400.),
+        Gain::Gain_4_7 => (400., 355.),
+        Gain::Gain_5_6 => (330., 295.),
+        Gain::Gain_8_1 => (230., 205.),
+    }
+}
+
+
+/// The full scale of a gain, in Gauss.
+pub(crate) fn full_scale(gain: Gain) -> f64 {
+    match gain {
+        Gain::Gain_1_3 => 1.3,
+        Gain::Gain_1_9 => 1.9,
+        Gain::Gain_2_5 => 2.5,
+        Gain::Gain_4_0 => 4.0,
+        Gain::Gain_4_7 => 4.7,
+        Gain::Gain_5_6 => 5.6,
+        Gain::Gain_8_1 => 8.1,
     }
 }
 
 
-/// Access the underlying `I2CDevice`.
+/// Access the underlying I2C bus.
 ///
 /// Most of the methods require a mutable reference; `DerefMut` is implemented as well.
 impl<Dev> Deref for Magnetometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     type Target = Dev;
 
@@ -235,12 +807,12 @@ where
 }
 
 
-/// Access the underlying `I2CDevice`.
+/// Access the underlying I2C bus.
 ///
 /// Refer to the LSM303 datasheet if you plan on accessing the device directly.
 impl<Dev> DerefMut for Magnetometer<Dev>
 where
-    Dev: I2CDevice,
+    Dev: I2c,
 {
     fn deref_mut(&mut self) -> &mut Dev {
         &mut self.device
@@ -0,0 +1,234 @@
+//! A simulated LSM303, for testing without hardware.
+//!
+//! `Device` implements the `embedded-hal` `I2c` trait, and holds a register
+//! file for both the accelerometer (`0x19`) and magnetometer (`0x1E`).
+//! Registers start at their reset values, writes to read-only registers
+//! are ignored, and the register address auto-increments on multi-byte
+//! transfers the same way the hardware does.
+//! Output registers can be loaded with `set_accelerometer_register` and
+//! `set_magnetometer_register`.
+//!
+//! This module is only available with the `mock` feature.
+//!
+//! ```
+//! # extern crate lsm303;
+//! # use lsm303::{mock, registers, Accelerometer};
+//! # use lsm303::accelerometer::Scale;
+//! # fn main() {
+//! let mut accel = Accelerometer::from_i2c_device(mock::Device::new()).unwrap();
+//! accel.set_scale(Scale::Scale4G).unwrap();
+//! assert_eq!(accel.accelerometer_register(registers::CTRL_REG4_A), 0x18);
+//! # }
+//! ```
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use registers::*;
+
+
+/// The I2C address of the accelerometer.
+pub(crate) const ACCELEROMETER_ADDRESS: u8 = 0x32 >> 1;
+
+
+/// The I2C address of the magnetometer.
+pub(crate) const MAGNETOMETER_ADDRESS: u8 = 0x3C >> 1;
+
+
+/// The accelerometer sets this bit of the register address to auto-increment.
+const AUTO_INCREMENT: u8 = 0x80;
+
+
+/// A simulated LSM303 on an I2C bus.
+pub struct Device {
+    accelerometer: RegisterFile,
+    magnetometer: RegisterFile,
+}
+
+
+/// The registers of one of the sensors, and its register address pointer.
+pub(crate) struct RegisterFile {
+    pub(crate) registers: [u8; 0x80],
+    pub(crate) pointer: u8,
+    pub(crate) increment: bool,
+    always_increment: bool,
+    read_only: &'static [u8],
+}
+
+
+/// Registers that the accelerometer does not allow writing to.
+const ACCELEROMETER_READ_ONLY: [u8; 11] = [
+    STATUS_REG_A,
+    OUT_X_L_A,
+    OUT_X_H_A,
+    OUT_Y_L_A,
+    OUT_Y_H_A,
+    OUT_Z_L_A,
+    OUT_Z_H_A,
+    FIFO_SRC_REG_A,
+    INT1_SOURCE_A,
+    INT2_SOURCE_A,
+    CLICK_SRC_A,
+];
+
+
+/// Registers that the magnetometer does not allow writing to.
+const MAGNETOMETER_READ_ONLY: [u8; 12] = [
+    OUT_X_H_M,
+    OUT_X_L_M,
+    OUT_Z_H_M,
+    OUT_Z_L_M,
+    OUT_Y_H_M,
+    OUT_Y_L_M,
+    SR_REG_M,
+    IRA_REG_M,
+    IRB_REG_M,
+    IRC_REG_M,
+    TEMP_OUT_H_M,
+    TEMP_OUT_L_M,
+];
+
+
+impl Device {
+    /// Create a device with every register at its reset value.
+    ///
+    /// See Table 17 of the datasheet.
+    pub fn new() -> Device {
+        Device {
+            accelerometer: RegisterFile::accelerometer(),
+            magnetometer: RegisterFile::magnetometer(),
+        }
+    }
+
+
+    /// The current value of an accelerometer register.
+    pub fn accelerometer_register(&self, register: u8) -> u8 {
+        self.accelerometer.registers[(register & !AUTO_INCREMENT) as usize]
+    }
+
+
+    /// Set an accelerometer register, including read-only registers.
+    pub fn set_accelerometer_register(&mut self, register: u8, value: u8) {
+        self.accelerometer.registers[(register & !AUTO_INCREMENT) as usize] = value;
+    }
+
+
+    /// The current value of a magnetometer register.
+    pub fn magnetometer_register(&self, register: u8) -> u8 {
+        self.magnetometer.registers[(register & !AUTO_INCREMENT) as usize]
+    }
+
+
+    /// Set a magnetometer register, including read-only registers.
+    pub fn set_magnetometer_register(&mut self, register: u8, value: u8) {
+        self.magnetometer.registers[(register & !AUTO_INCREMENT) as usize] = value;
+    }
+}
+
+
+impl Default for Device {
+    fn default() -> Device {
+        Device::new()
+    }
+}
+
+
+impl RegisterFile {
+    /// The accelerometer registers, at their reset values.
+    ///
+    /// The accelerometer only auto-increments when the MSB
+    /// of the register address is set.
+    pub(crate) fn accelerometer() -> RegisterFile {
+        let mut file = RegisterFile::new(&ACCELEROMETER_READ_ONLY, false);
+        file.registers[CTRL_REG1_A as usize] = 0x07;
+        file
+    }
+
+    /// The magnetometer registers, at their reset values.
+    ///
+    /// The magnetometer always auto-increments.
+    pub(crate) fn magnetometer() -> RegisterFile {
+        let mut file = RegisterFile::new(&MAGNETOMETER_READ_ONLY, true);
+        file.registers[CRA_REG_M as usize] = 0x10;
+        file.registers[CRB_REG_M as usize] = 0x20;
+        file.registers[MR_REG_M as usize] = 0x03;
+        file.registers[IRA_REG_M as usize] = b'H';
+        file.registers[IRB_REG_M as usize] = b'4';
+        file.registers[IRC_REG_M as usize] = b'3';
+        file
+    }
+
+    fn new(read_only: &'static [u8], always_increment: bool) -> RegisterFile {
+        RegisterFile {
+            registers: [0; 0x80],
+            pointer: 0,
+            increment: false,
+            always_increment,
+            read_only,
+        }
+    }
+
+    /// Set the register address pointer from the first byte of a write.
+    pub(crate) fn select(&mut self, address: u8) {
+        self.pointer = address & !AUTO_INCREMENT;
+        self.increment = self.always_increment || address & AUTO_INCREMENT != 0;
+    }
+
+    /// Read the register at the pointer.
+    pub(crate) fn read(&mut self) -> u8 {
+        let value = self.registers[self.pointer as usize];
+        self.advance();
+        value
+    }
+
+    /// Write the register at the pointer, unless it is read-only.
+    pub(crate) fn write(&mut self, value: u8) {
+        if !self.read_only.contains(&self.pointer) {
+            self.registers[self.pointer as usize] = value;
+        }
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        if self.increment {
+            self.pointer = (self.pointer + 1) & !AUTO_INCREMENT;
+        }
+    }
+}
+
+
+impl ErrorType for Device {
+    type Error = ErrorKind;
+}
+
+
+impl I2c for Device {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation],
+    ) -> Result<(), ErrorKind> {
+        let registers = match address {
+            ACCELEROMETER_ADDRESS => &mut self.accelerometer,
+            MAGNETOMETER_ADDRESS => &mut self.magnetometer,
+            _ => return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+        };
+
+        let mut selected = false;
+        for operation in operations {
+            match *operation {
+                Operation::Write(bytes) => for &byte in bytes {
+                    if selected {
+                        registers.write(byte);
+                    } else {
+                        registers.select(byte);
+                        selected = true;
+                    }
+                },
+                Operation::Read(ref mut buffer) => for byte in buffer.iter_mut() {
+                    *byte = registers.read();
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,442 @@
+//! Record I2C transactions to a file, and replay them later.
+//!
+//! `Recorder` wraps any I2C bus, and logs every transaction that passes
+//! through it, along with a timestamp. `Replay` serves a recording back,
+//! so that the driver can be run against it without the original hardware.
+//!
+//! The log is plain text, with one transaction per line:
+//!
+//! ```text
+//! 1760000000.000123 19 w:a8 r:00000000803e
+//! 1760000000.000456 19 w:2027
+//! 1760000000.000789 1e w:03 r:000000000000 err:nack-address
+//! ```
+//!
+//! Each line holds the time since the Unix epoch, the 7-bit device address,
+//! the bytes written (`w:`) and read (`r:`) by each operation in hex,
+//! and the kind of bus error if the transaction failed.
+//! Lines starting with `#` are ignored.
+//!
+//! This module requires the `std` feature.
+//!
+//! ```no_run
+//! # extern crate linux_embedded_hal;
+//! # extern crate lsm303;
+//! # use linux_embedded_hal::I2cdev;
+//! # use lsm303::Accelerometer;
+//! # use lsm303::recording::{Recorder, Replay};
+//! # fn main() { test().unwrap(); }
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! // On the device:
+//! let device = Recorder::create(I2cdev::new("/dev/i2c-1")?, "accel.log")?;
+//! let mut accel = Accelerometer::from_i2c_device(device)?;
+//! let acceleration = accel.read_acceleration()?;
+//!
+//! // Elsewhere:
+//! let mut accel = Accelerometer::from_i2c_device(Replay::open("accel.log")?)?;
+//! assert_eq!(accel.read_acceleration()?, acceleration);
+//! # Ok(())
+//! # }
+//! ```
+
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+
+/// An I2C bus that logs every transaction to a writer.
+pub struct Recorder<Dev, W>
+where
+    Dev: I2c,
+    W: Write,
+{
+    device: Dev,
+    log: W,
+}
+
+
+/// An error from a `Recorder`.
+#[derive(Debug)]
+pub enum RecorderError<E> {
+    /// The underlying bus returned an error, which was recorded.
+    Bus(E),
+    /// The transaction could not be written to the log.
+    Io(io::Error),
+}
+
+
+/// An I2C bus that replays a recording.
+///
+/// Each transaction must match the next one in the recording,
+/// in its address, the bytes written and the number of bytes read.
+pub struct Replay {
+    transactions: Vec<Transaction>,
+    next: usize,
+}
+
+
+/// An error from a `Replay`.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recorded transaction failed with this error.
+    Recorded(ErrorKind),
+    /// The transaction did not match the recording.
+    Mismatch {
+        /// The line of the recording that was expected.
+        line: usize,
+    },
+    /// There are no more transactions in the recording.
+    EndOfRecording,
+}
+
+
+/// A single recorded transaction.
+struct Transaction {
+    line: usize,
+    timestamp: Duration,
+    address: u8,
+    operations: Vec<RecordedOperation>,
+    error: Option<ErrorKind>,
+}
+
+
+enum RecordedOperation {
+    Write(Vec<u8>),
+    Read(Vec<u8>),
+}
+
+
+impl<Dev> Recorder<Dev, BufWriter<File>>
+where
+    Dev: I2c,
+{
+    /// Record the transactions on `device` to a new file.
+    ///
+    /// The file is truncated if it already exists.
+    pub fn create<P>(device: Dev, path: P) -> io::Result<Recorder<Dev, BufWriter<File>>>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(path)?;
+        Recorder::new(device, BufWriter::new(file))
+    }
+}
+
+
+impl<Dev, W> Recorder<Dev, W>
+where
+    Dev: I2c,
+    W: Write,
+{
+    /// Record the transactions on `device` to `log`.
+    pub fn new(device: Dev, mut log: W) -> io::Result<Recorder<Dev, W>> {
+        writeln!(log, "# lsm303 i2c recording")?;
+        Ok(Recorder { device, log })
+    }
+
+
+    /// Stop recording, returning the underlying bus and the log.
+    pub fn into_inner(self) -> (Dev, W) {
+        (self.device, self.log)
+    }
+
+
+    /// Record a transaction, then pass on its result.
+    fn finish(
+        &mut self,
+        address: u8,
+        operations: &[Operation],
+        result: Result<(), Dev::Error>,
+    ) -> Result<(), RecorderError<Dev::Error>> {
+        use embedded_hal::i2c::Error;
+
+        let kind = result.as_ref().err().map(|e| e.kind());
+        self.record(address, operations, kind)
+            .map_err(RecorderError::Io)?;
+        result.map_err(RecorderError::Bus)
+    }
+
+
+    fn record(
+        &mut self,
+        address: u8,
+        operations: &[Operation],
+        error: Option<ErrorKind>,
+    ) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let mut line = format!(
+            "{}.{:06} {:02x}",
+            timestamp.as_secs(),
+            timestamp.subsec_micros(),
+            address
+        );
+        for operation in operations {
+            let (tag, bytes): (&str, &[u8]) = match *operation {
+                Operation::Write(bytes) => ("w", bytes),
+                Operation::Read(ref bytes) => ("r", bytes),
+            };
+            let _ = write!(line, " {}:", tag);
+            for byte in bytes {
+                let _ = write!(line, "{:02x}", byte);
+            }
+        }
+        if let Some(kind) = error {
+            let _ = write!(line, " err:{}", kind_name(kind));
+        }
+
+        writeln!(self.log, "{}", line)?;
+        // Keep the log intact if the program stops unexpectedly.
+        self.log.flush()
+    }
+}
+
+
+impl<Dev, W> ErrorType for Recorder<Dev, W>
+where
+    Dev: I2c,
+    W: Write,
+{
+    type Error = RecorderError<Dev::Error>;
+}
+
+
+impl<Dev, W> I2c for Recorder<Dev, W>
+where
+    Dev: I2c,
+    W: Write,
+{
+    // Forward each method to the same method of the underlying bus,
+    // rather than to its `transaction`, in case it implements them differently.
+
+    fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        let result = self.device.read(address, read);
+        self.finish(address, &[Operation::Read(read)], result)
+    }
+
+
+    fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        let result = self.device.write(address, write);
+        self.finish(address, &[Operation::Write(write)], result)
+    }
+
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let result = self.device.write_read(address, write, read);
+        self.finish(address, &[Operation::Write(write), Operation::Read(read)], result)
+    }
+
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation],
+    ) -> Result<(), Self::Error> {
+        let result = self.device.transaction(address, operations);
+        self.finish(address, operations, result)
+    }
+}
+
+
+impl<E> i2c::Error for RecorderError<E>
+where
+    E: i2c::Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            RecorderError::Bus(ref error) => error.kind(),
+            RecorderError::Io(_) => ErrorKind::Other,
+        }
+    }
+}
+
+
+impl Replay {
+    /// Load a recording from a file.
+    pub fn open<P>(path: P) -> io::Result<Replay>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        Replay::from_reader(BufReader::new(file))
+    }
+
+
+    /// Load a recording from a reader.
+    pub fn from_reader<R>(reader: R) -> io::Result<Replay>
+    where
+        R: BufRead,
+    {
+        let mut transactions = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let transaction = parse_transaction(index + 1, line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid recording on line {}", index + 1),
+                )
+            })?;
+            transactions.push(transaction);
+        }
+
+        Ok(Replay {
+            transactions,
+            next: 0,
+        })
+    }
+
+
+    /// The number of transactions that have not been replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.transactions.len() - self.next
+    }
+
+
+    /// The time at which the last replayed transaction was recorded,
+    /// since the Unix epoch.
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.next
+            .checked_sub(1)
+            .map(|last| self.transactions[last].timestamp)
+    }
+}
+
+
+impl ErrorType for Replay {
+    type Error = ReplayError;
+}
+
+
+impl I2c for Replay {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation],
+    ) -> Result<(), ReplayError> {
+        let recorded = match self.transactions.get(self.next) {
+            Some(recorded) => recorded,
+            None => return Err(ReplayError::EndOfRecording),
+        };
+
+        let matches = recorded.address == address &&
+            recorded.operations.len() == operations.len() &&
+            recorded.operations.iter().zip(operations.iter()).all(|pair| match pair {
+                (RecordedOperation::Write(a), Operation::Write(b)) => a[..] == b[..],
+                (RecordedOperation::Read(a), Operation::Read(b)) => a.len() == b.len(),
+                _ => false,
+            });
+        if !matches {
+            return Err(ReplayError::Mismatch { line: recorded.line });
+        }
+        self.next += 1;
+
+        if let Some(kind) = recorded.error {
+            return Err(ReplayError::Recorded(kind));
+        }
+        for (recorded, operation) in recorded.operations.iter().zip(operations.iter_mut()) {
+            if let (RecordedOperation::Read(data), Operation::Read(buffer)) = (recorded, operation) {
+                buffer.copy_from_slice(data);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+impl i2c::Error for ReplayError {
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            ReplayError::Recorded(kind) => kind,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+
+/// Parse one line of a recording.
+fn parse_transaction(line: usize, text: &str) -> Option<Transaction> {
+    let mut fields = text.split_whitespace();
+
+    let mut timestamp = fields.next()?.splitn(2, '.');
+    let secs = timestamp.next()?.parse().ok()?;
+    let micros = timestamp.next()?.parse().ok()?;
+    let timestamp = Duration::new(secs, 0) + Duration::from_micros(micros);
+
+    let address = u8::from_str_radix(fields.next()?, 16).ok()?;
+
+    let mut operations = Vec::new();
+    let mut error = None;
+    for field in fields {
+        let (tag, value) = field.split_at(field.find(':')?);
+        let value = &value[1..];
+        match tag {
+            "w" => operations.push(RecordedOperation::Write(parse_hex(value)?)),
+            "r" => operations.push(RecordedOperation::Read(parse_hex(value)?)),
+            "err" => error = Some(parse_kind(value)?),
+            _ => return None,
+        }
+    }
+
+    Some(Transaction {
+        line,
+        timestamp,
+        address,
+        operations,
+        error,
+    })
+}
+
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair.len() {
+            2 => u8::from_str_radix(::std::str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+
+/// The name of an error kind in a recording.
+fn kind_name(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Bus => "bus",
+        ErrorKind::ArbitrationLoss => "arbitration-loss",
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => "nack-address",
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data) => "nack-data",
+        ErrorKind::NoAcknowledge(_) => "nack",
+        ErrorKind::Overrun => "overrun",
+        _ => "other",
+    }
+}
+
+
+fn parse_kind(name: &str) -> Option<ErrorKind> {
+    let kind = match name {
+        "bus" => ErrorKind::Bus,
+        "arbitration-loss" => ErrorKind::ArbitrationLoss,
+        "nack-address" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        "nack-data" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        "nack" => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+        "overrun" => ErrorKind::Overrun,
+        "other" => ErrorKind::Other,
+        _ => return None,
+    };
+    Some(kind)
+}
@@ -2,6 +2,7 @@
 // It is derived directly from the datasheet,
 // which should serve as its best documentation.
 #![allow(missing_docs)]
+#![allow(non_upper_case_globals)]
 
 //! Type and address definitions for most of the LSM303 registers.
 //!
@@ -14,32 +15,38 @@
 //! It also defines bitflags for most of the registers.
 //! Refer to the [datasheet](http://www.st.com/resource/en/datasheet/lsm303dlhc.pdf)
 //! for more extensive documentation.
+//!
+//! The definitions in this module are for the LSM303DLHC.
+//! The other variants supported by `variant::Driver` are defined in
+//! `lsm303d`, `lsm303agr` and `lsm303c`, with bitflags for the
+//! registers that the driver configures.
 
 /// Read a register and convert to a bitflag.
 ///
-/// ```
-/// let mut flags = read_register(self.device, CRA_REG_M, CraRegM)?;
+/// ```ignore
+/// let mut flags = read_register!(self.device, I2C_ADDRESS, SENSOR, CRA_REG_M, CraRegM)?;
 /// ```
 macro_rules! read_register {
-    ( $device:expr, $register:expr, $flag_type:ident ) => {
+    ( $device:expr, $address:expr, $sensor:expr, $register:expr, $flag_type:ident ) => {{
+        let mut buffer = [0];
         $device
-            .smbus_read_byte_data($register)
-            .chain_err(|| ErrorKind::FailedToReadRegister)
-            .map($flag_type::from_bits_truncate)
-    }
+            .write_read($address, &[$register], &mut buffer)
+            .map_err(|e| ::errors::Error::read($sensor, $register, e))
+            .map(|()| $flag_type::from_bits_truncate(buffer[0]))
+    }}
 }
 
 
 /// Write a bitflag to a register.
 ///
-/// ```
-/// write_register!(self.device, CRA_REG_M, flags)?;
+/// ```ignore
+/// write_register!(self.device, I2C_ADDRESS, SENSOR, CRA_REG_M, flags)?;
 /// ```
 macro_rules! write_register {
-    ( $device:expr, $register:expr, $bitflag:ident ) => {
+    ( $device:expr, $address:expr, $sensor:expr, $register:expr, $bitflag:ident ) => {
         $device
-            .smbus_write_byte_data($register, $bitflag.bits())
-            .chain_err(|| ErrorKind::FailedToWriteRegister)
+            .write($address, &[$register, $bitflag.bits()])
+            .map_err(|e| ::errors::Error::write($sensor, $register, e))
     }
 }
 
@@ -67,7 +74,7 @@ register_addresses! {
     0x26 => REFERENCE_A;
     0x27 => STATUS_REG_A;
     0x28 => OUT_X_L_A;
-    0x29 => OUT_X_H_H;
+    0x29 => OUT_X_H_A;
     0x2A => OUT_Y_L_A;
     0x2B => OUT_Y_H_A;
     0x2C => OUT_Z_L_A;
@@ -156,7 +163,7 @@ define_registers!{
         7, ODR3        | 6, ODR2        | 5, ODR1        | 4, ODR0        |
         3, LPen        | 2, Zen         | 1, Yen         | 0, Xen         |
     }
-    CtrlReg2Af {
+    CtrlReg2A {
         7, HPM1        | 6, HPM0        | 5, HPCF2       | 4, HPCF1       |
         3, FDS         | 2, HPCLICK     | 1, HPIS2       | 0, HPIS1       |
     }
@@ -193,7 +200,7 @@ define_registers!{
         3, FSS3        | 2, FSS2        | 1, FSS1        | 0, FSS0        |
     }
     IntCfgA {
-        7, AOI         | 6, _6D         | 5, ZHIE        | 4, ZLIO        |
+        7, AOI         | 6, _6D         | 5, ZHIE        | 4, ZLIE        |
         3, YHIE        | 2, YLIE        | 1, XHIE        | 0, XLIE        |
     }
     IntSrcA {
@@ -209,7 +216,7 @@ define_registers!{
         3, YD          | 2, YS          | 1, XD          | 0, XS          |
     }
     ClickSrcA {
-        /* ---------- */ 6, IA_click    | 5, DCLICK      | 5, SCLICK      |
+        /* ---------- */ 6, IA_click    | 5, DCLICK      | 4, SCLICK      |
         3, Sign        | 2, Z           | 1, Y           | 0, X           |
     }
 
@@ -232,3 +239,25 @@ define_registers!{
         /* ----------- | ------------- */ 1, LOCK        | 0, DRDY        |
     }
 }
+
+
+
+// Earlier names, which did not match the datasheet.
+
+#[deprecated(note = "renamed to OUT_X_H_A")]
+pub const OUT_X_H_H: u8 = OUT_X_H_A;
+
+#[deprecated(note = "renamed to CtrlReg2A")]
+pub type CtrlReg2Af = CtrlReg2A;
+
+impl IntCfgA {
+    #[deprecated(note = "renamed to ZLIE")]
+    pub const ZLIO: IntCfgA = IntCfgA::ZLIE;
+}
+
+
+pub mod lsm303d;
+
+pub mod lsm303agr;
+
+pub mod lsm303c;
@@ -0,0 +1,118 @@
+//! Register definitions for the LSM303AGR.
+//!
+//! The accelerometer is similar to the LSM303DLHC,
+//! but the magnetometer is entirely different.
+//! Refer to the [datasheet](http://www.st.com/resource/en/datasheet/lsm303agr.pdf).
+
+// Table 24 of the LSM303AGR datasheet.
+register_addresses! {
+    // Accelerometer
+
+    // 0x00 - 0x06 => reserved
+    0x07 => STATUS_REG_AUX_A;
+    // 0x08 - 0x0B => reserved
+    0x0C => OUT_TEMP_L_A;
+    0x0D => OUT_TEMP_H_A;
+    0x0E => INT_COUNTER_REG_A;
+    0x0F => WHO_AM_I_A;
+    // 0x10 - 0x1E => reserved
+    0x1F => TEMP_CFG_REG_A;
+    0x20 => CTRL_REG1_A;
+    0x21 => CTRL_REG2_A;
+    0x22 => CTRL_REG3_A;
+    0x23 => CTRL_REG4_A;
+    0x24 => CTRL_REG5_A;
+    0x25 => CTRL_REG6_A;
+    0x26 => REFERENCE_DATACAPTURE_A;
+    0x27 => STATUS_REG_A;
+    0x28 => OUT_X_L_A;
+    0x29 => OUT_X_H_A;
+    0x2A => OUT_Y_L_A;
+    0x2B => OUT_Y_H_A;
+    0x2C => OUT_Z_L_A;
+    0x2D => OUT_Z_H_A;
+    0x2E => FIFO_CTRL_REG_A;
+    0x2F => FIFO_SRC_REG_A;
+    0x30 => INT1_CFG_A;
+    0x31 => INT1_SRC_A;
+    0x32 => INT1_THS_A;
+    0x33 => INT1_DURATION_A;
+    0x34 => INT2_CFG_A;
+    0x35 => INT2_SRC_A;
+    0x36 => INT2_THS_A;
+    0x37 => INT2_DURATION_A;
+    0x38 => CLICK_CFG_A;
+    0x39 => CLICK_SRC_A;
+    0x3A => CLICK_THS_A;
+    0x3B => TIME_LIMIT_A;
+    0x3C => TIME_LATENCY_A;
+    0x3D => TIME_WINDOW_A;
+    0x3E => ACT_THS_A;
+    0x3F => ACT_DUR_A;
+
+    // Magnetometer
+
+    // 0x40 - 0x44 => reserved
+    0x45 => OFFSET_X_REG_L_M;
+    0x46 => OFFSET_X_REG_H_M;
+    0x47 => OFFSET_Y_REG_L_M;
+    0x48 => OFFSET_Y_REG_H_M;
+    0x49 => OFFSET_Z_REG_L_M;
+    0x4A => OFFSET_Z_REG_H_M;
+    // 0x4B - 0x4E => reserved
+    0x4F => WHO_AM_I_M;
+    // 0x50 - 0x5F => reserved
+    0x60 => CFG_REG_A_M;
+    0x61 => CFG_REG_B_M;
+    0x62 => CFG_REG_C_M;
+    0x63 => INT_CRTL_REG_M;
+    0x64 => INT_SOURCE_REG_M;
+    0x65 => INT_THS_L_REG_M;
+    0x66 => INT_THS_H_REG_M;
+    0x67 => STATUS_REG_M;
+    0x68 => OUTX_L_REG_M;
+    0x69 => OUTX_H_REG_M;
+    0x6A => OUTY_L_REG_M;
+    0x6B => OUTY_H_REG_M;
+    0x6C => OUTZ_L_REG_M;
+    0x6D => OUTZ_H_REG_M;
+}
+
+
+/// The value of `WHO_AM_I_A`.
+pub const WHO_AM_I_A_VALUE: u8 = 0x33;
+
+
+/// The value of `WHO_AM_I_M`.
+pub const WHO_AM_I_M_VALUE: u8 = 0x40;
+
+
+// Section 8 of the LSM303AGR datasheet.
+define_registers!{
+
+    // Accelerometer
+
+    TempCfgRegA {
+        7, TEMP_EN1    | 6, TEMP_EN0    | /* ----------- | ------------- */
+        /* ----------- | -------------- | -------------- | ------------- */
+    }
+    CtrlReg1A {
+        7, ODR3        | 6, ODR2        | 5, ODR1        | 4, ODR0        |
+        3, LPen        | 2, Zen         | 1, Yen         | 0, Xen         |
+    }
+    CtrlReg4A {
+        7, BDU         | 6, BLE         | 5, FS1         | 4, FS0         |
+        3, HR          | 2, ST1         | 1, ST0         | 0, SPI_ENABLE  |
+    }
+
+    // Magnetometer
+
+    CfgRegAM {
+        7, COMP_TEMP_EN| 6, REBOOT      | 5, SOFT_RST    | 4, LP          |
+        3, ODR1        | 2, ODR0        | 1, MD1         | 0, MD0         |
+    }
+    CfgRegCM {
+        /* ---------- */ 6, INT_MAG_PIN | 5, I2C_DIS     | 4, BDU         |
+        3, BLE         | /* ---------- */ 1, Self_test   | 0, INT_MAG     |
+    }
+}
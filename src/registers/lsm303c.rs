@@ -0,0 +1,120 @@
+//! Register definitions for the LSM303C.
+//!
+//! The accelerometer and magnetometer are at different I2C addresses,
+//! and their register addresses overlap.
+//! Refer to the [datasheet](http://www.st.com/resource/en/datasheet/lsm303c.pdf).
+
+// Table 17 of the LSM303C datasheet.
+register_addresses! {
+    // Accelerometer
+
+    // 0x00 - 0x0E => reserved
+    0x0F => WHO_AM_I_A;
+    // 0x10 - 0x1D => reserved
+    0x1E => ACT_THS_A;
+    0x1F => ACT_DUR_A;
+    0x20 => CTRL_REG1_A;
+    0x21 => CTRL_REG2_A;
+    0x22 => CTRL_REG3_A;
+    0x23 => CTRL_REG4_A;
+    0x24 => CTRL_REG5_A;
+    0x25 => CTRL_REG6_A;
+    0x26 => CTRL_REG7_A;
+    0x27 => STATUS_REG_A;
+    0x28 => OUT_X_L_A;
+    0x29 => OUT_X_H_A;
+    0x2A => OUT_Y_L_A;
+    0x2B => OUT_Y_H_A;
+    0x2C => OUT_Z_L_A;
+    0x2D => OUT_Z_H_A;
+    0x2E => FIFO_CTRL;
+    0x2F => FIFO_SRC;
+    0x30 => IG_CFG1_A;
+    0x31 => IG_SRC1_A;
+    0x32 => IG_THS_X1_A;
+    0x33 => IG_THS_Y1_A;
+    0x34 => IG_THS_Z1_A;
+    0x35 => IG_DUR1_A;
+    0x36 => IG_CFG2_A;
+    0x37 => IG_SRC2_A;
+    0x38 => IG_THS2_A;
+    0x39 => IG_DUR2_A;
+    0x3A => XL_REFERENCE;
+    0x3B => XH_REFERENCE;
+    0x3C => YL_REFERENCE;
+    0x3D => YH_REFERENCE;
+    0x3E => ZL_REFERENCE;
+    0x3F => ZH_REFERENCE;
+
+    // Magnetometer
+
+    // 0x00 - 0x0E => reserved
+    0x0F => WHO_AM_I_M;
+    // 0x10 - 0x1F => reserved
+    0x20 => CTRL_REG1_M;
+    0x21 => CTRL_REG2_M;
+    0x22 => CTRL_REG3_M;
+    0x23 => CTRL_REG4_M;
+    0x24 => CTRL_REG5_M;
+    // 0x25 - 0x26 => reserved
+    0x27 => STATUS_REG_M;
+    0x28 => OUT_X_L_M;
+    0x29 => OUT_X_H_M;
+    0x2A => OUT_Y_L_M;
+    0x2B => OUT_Y_H_M;
+    0x2C => OUT_Z_L_M;
+    0x2D => OUT_Z_H_M;
+    0x2E => TEMP_L_M;
+    0x2F => TEMP_H_M;
+    0x30 => INT_CFG_M;
+    0x31 => INT_SRC_M;
+    0x32 => INT_THS_L_M;
+    0x33 => INT_THS_H_M;
+}
+
+
+/// The value of `WHO_AM_I_A`.
+pub const WHO_AM_I_A_VALUE: u8 = 0x41;
+
+
+/// The value of `WHO_AM_I_M`.
+pub const WHO_AM_I_M_VALUE: u8 = 0x3D;
+
+
+// Section 8 and 9 of the LSM303C datasheet.
+define_registers!{
+
+    // Accelerometer
+
+    CtrlReg1A {
+        7, HR          | 6, ODR2        | 5, ODR1        | 4, ODR0        |
+        3, BDU         | 2, Zen         | 1, Yen         | 0, Xen         |
+    }
+    CtrlReg4A {
+        7, BW2         | 6, BW1         | 5, FS1         | 4, FS0         |
+        3, BW_SCALE_ODR| 2, IF_ADD_INC  | 1, I2C_DISABLE | 0, SIM         |
+    }
+
+    // Magnetometer
+
+    CtrlReg1M {
+        7, TEMP_EN     | 6, OM1         | 5, OM0         | 4, DO2         |
+        3, DO1         | 2, DO0         | /* ---------- */ 0, ST          |
+    }
+    CtrlReg2M {
+        /* ---------- */ 6, FS1         | 5, FS0         | /* ---------- */
+        3, REBOOT      | 2, SOFT_RST    | /* ----------- | ------------- */
+    }
+    CtrlReg3M {
+        7, I2C_DISABLE | /* ---------- */ 5, LP          | /* ---------- */
+        /* ---------- */ 2, SIM         | 1, MD1         | 0, MD0         |
+    }
+    CtrlReg4M {
+        /* ----------- | -------------- | -------------- | ------------- */
+        3, OMZ1        | 2, OMZ0        | 1, BLE         | /* ---------- */
+    }
+    CtrlReg5M {
+        /* ---------- */ 6, BDU         | /* ----------- | ------------- */
+        /* ----------- | -------------- | -------------- | ------------- */
+    }
+}
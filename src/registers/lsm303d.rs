@@ -0,0 +1,97 @@
+//! Register definitions for the LSM303D.
+//!
+//! The accelerometer and magnetometer share one I2C address,
+//! and a single register map.
+//! Refer to the [datasheet](http://www.st.com/resource/en/datasheet/lsm303d.pdf).
+
+// Table 16 of the LSM303D datasheet.
+register_addresses! {
+    // 0x00 - 0x04 => reserved
+    0x05 => TEMP_OUT_L;
+    0x06 => TEMP_OUT_H;
+    0x07 => STATUS_M;
+    0x08 => OUT_X_L_M;
+    0x09 => OUT_X_H_M;
+    0x0A => OUT_Y_L_M;
+    0x0B => OUT_Y_H_M;
+    0x0C => OUT_Z_L_M;
+    0x0D => OUT_Z_H_M;
+    // 0x0E => reserved
+    0x0F => WHO_AM_I;
+    // 0x10 - 0x11 => reserved
+    0x12 => INT_CTRL_M;
+    0x13 => INT_SRC_M;
+    0x14 => INT_THS_L_M;
+    0x15 => INT_THS_H_M;
+    0x16 => OFFSET_X_L_M;
+    0x17 => OFFSET_X_H_M;
+    0x18 => OFFSET_Y_L_M;
+    0x19 => OFFSET_Y_H_M;
+    0x1A => OFFSET_Z_L_M;
+    0x1B => OFFSET_Z_H_M;
+    0x1C => REFERENCE_X;
+    0x1D => REFERENCE_Y;
+    0x1E => REFERENCE_Z;
+    0x1F => CTRL0;
+    0x20 => CTRL1;
+    0x21 => CTRL2;
+    0x22 => CTRL3;
+    0x23 => CTRL4;
+    0x24 => CTRL5;
+    0x25 => CTRL6;
+    0x26 => CTRL7;
+    0x27 => STATUS_A;
+    0x28 => OUT_X_L_A;
+    0x29 => OUT_X_H_A;
+    0x2A => OUT_Y_L_A;
+    0x2B => OUT_Y_H_A;
+    0x2C => OUT_Z_L_A;
+    0x2D => OUT_Z_H_A;
+    0x2E => FIFO_CTRL;
+    0x2F => FIFO_SRC;
+    0x30 => IG_CFG1;
+    0x31 => IG_SRC1;
+    0x32 => IG_THS1;
+    0x33 => IG_DUR1;
+    0x34 => IG_CFG2;
+    0x35 => IG_SRC2;
+    0x36 => IG_THS2;
+    0x37 => IG_DUR2;
+    0x38 => CLICK_CFG;
+    0x39 => CLICK_SRC;
+    0x3A => CLICK_THS;
+    0x3B => TIME_LIMIT;
+    0x3C => TIME_LATENCY;
+    0x3D => TIME_WINDOW;
+    0x3E => ACT_THS;
+    0x3F => ACT_DUR;
+}
+
+
+/// The value of `WHO_AM_I`.
+pub const WHO_AM_I_VALUE: u8 = 0x49;
+
+
+// Section 8 of the LSM303D datasheet.
+define_registers!{
+    Ctrl1 {
+        7, AODR3       | 6, AODR2       | 5, AODR1       | 4, AODR0       |
+        3, BDU         | 2, AZEN        | 1, AYEN        | 0, AXEN        |
+    }
+    Ctrl2 {
+        7, ABW1        | 6, ABW0        | 5, AFS2        | 4, AFS1        |
+        3, AFS0        | 2, AST         | /* ---------- */ 0, SIM         |
+    }
+    Ctrl5 {
+        7, TEMP_EN     | 6, M_RES1      | 5, M_RES0      | 4, M_ODR2      |
+        3, M_ODR1      | 2, M_ODR0      | 1, LIR2        | 0, LIR1        |
+    }
+    Ctrl6 {
+        /* ---------- */ 6, MFS1        | 5, MFS0        | /* ---------- */
+        /* ----------- | -------------- | -------------- | ------------- */
+    }
+    Ctrl7 {
+        7, AHPM1       | 6, AHPM0       | 5, AFDS        | 4, T_ONLY      |
+        /* ---------- */ 2, MLP         | 1, MD1         | 0, MD0         |
+    }
+}
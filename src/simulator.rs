@@ -0,0 +1,714 @@
+//! A simulated LSM303 driven by a motion trajectory.
+//!
+//! Where `mock::Device` only holds registers, `Simulator` also produces
+//! measurements. On a simulated clock, it samples a `Trajectory` at the
+//! configured data rates, and converts the result to counts using the
+//! configured scale, gain and operating mode. Noise, bias and saturation
+//! are applied along the way. The data-ready bits, the accelerometer FIFO,
+//! and single measurements on the magnetometer behave as on the device.
+//!
+//! The clock only moves when `advance` is called, or by a fixed amount
+//! per I2C transaction if `Config::transaction_time` is set.
+//! The latter lets methods that poll for new data make progress.
+//!
+//! This module is only available with the `simulator` feature.
+//!
+//! ```
+//! # extern crate lsm303;
+//! # use std::f64::consts::PI;
+//! # use std::time::Duration;
+//! # use lsm303::units::{si, value};
+//! # use lsm303::common::Vector3;
+//! # use lsm303::simulator::{Config, Orientation, Simulator};
+//! # use lsm303::Accelerometer;
+//! # fn main() {
+//! // Level, and turning at a quarter turn per second.
+//! let trajectory = |t: si::Second<f64>| {
+//!     let orientation = Orientation {
+//!         roll: 0.0,
+//!         pitch: 0.0,
+//!         yaw: PI / 2.0 * value(t / si::S),
+//!     };
+//!     let zero = 0.0 * si::MPS2;
+//!     let still = Vector3 { x: zero, y: zero, z: zero };
+//!     let field = Vector3 { x: 20e-6 * si::T, y: 0.0 * si::T, z: -40e-6 * si::T };
+//!     orientation.sample(still, field)
+//! };
+//!
+//! let mut simulator = Simulator::new(trajectory, Config::new());
+//! simulator.advance(Duration::from_secs(1));
+//! let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+//! accel.advance(Duration::from_millis(100));
+//! let a = accel.read_acceleration().unwrap();
+//! assert!((a.z / si::MPS2 - 9.81).abs() < 0.05);
+//! # }
+//! ```
+
+use accelerometer::{self, AccelerationVector, OperatingMode, Scale};
+use common::{round, Vector3};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use magnetometer::{self, Gain, MagneticField, OVERFLOW};
+use mock::{RegisterFile, ACCELEROMETER_ADDRESS, MAGNETOMETER_ADDRESS};
+use registers::*;
+use std::collections::VecDeque;
+use core::ops::{Add, Mul, Sub};
+use std::time::Duration;
+use units::{gauss, si, value};
+
+
+/// The number of samples the accelerometer FIFO holds.
+const FIFO_SIZE: usize = 32;
+
+
+/// What the sensors experience at an instant, in the frame of the sensor.
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    /// The specific force, which includes the reaction to gravity.
+    /// A sensor lying flat and still reads +1 g on Z.
+    pub acceleration: AccelerationVector,
+    /// The magnetic field.
+    pub magnetic_field: MagneticField,
+}
+
+
+/// The motion that the simulated sensor goes through.
+///
+/// This is implemented for closures that take the simulated time.
+pub trait Trajectory {
+    /// What the sensors experience at the given time.
+    fn sample(&mut self, time: si::Second<f64>) -> Sample;
+}
+
+
+impl<F> Trajectory for F
+where
+    F: FnMut(si::Second<f64>) -> Sample,
+{
+    fn sample(&mut self, time: si::Second<f64>) -> Sample {
+        self(time)
+    }
+}
+
+
+/// The orientation of the sensor, as Euler angles in radians.
+///
+/// The world frame has X pointing north, Y west, and Z up.
+/// With every angle at zero, the sensor axes line up with the world axes.
+/// The sensor is rotated by `yaw` about Z, then `pitch` about the new Y,
+/// then `roll` about the new X; positive angles are counterclockwise
+/// when looking down the axis towards the origin.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Orientation {
+    /// Rotation about the X axis.
+    pub roll: f64,
+    /// Rotation about the Y axis.
+    pub pitch: f64,
+    /// Rotation about the Z axis.
+    pub yaw: f64,
+}
+
+
+impl Orientation {
+    /// What the sensors experience in this orientation.
+    ///
+    /// `acceleration` is the acceleration of the sensor in the world frame,
+    /// not including gravity. `field` is the magnetic field in the world frame.
+    pub fn sample(&self, acceleration: AccelerationVector, field: MagneticField) -> Sample {
+        let g = 9.80665 * si::MPS2;
+        let specific_force = Vector3 {
+            x: acceleration.x,
+            y: acceleration.y,
+            z: acceleration.z + g,
+        };
+        Sample {
+            acceleration: self.sensor_frame(specific_force),
+            magnetic_field: self.sensor_frame(field),
+        }
+    }
+
+    /// Rotate a vector from the world frame to the sensor frame.
+    fn sensor_frame<T>(&self, v: Vector3<T>) -> Vector3<T>
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+    {
+        let Vector3 { x, y, z } = v;
+
+        // Undo the yaw, then the pitch, then the roll.
+        let (s, c) = self.yaw.sin_cos();
+        let (x, y) = (x * c + y * s, y * c - x * s);
+        let (s, c) = self.pitch.sin_cos();
+        let (x, z) = (x * c - z * s, x * s + z * c);
+        let (s, c) = self.roll.sin_cos();
+        let (y, z) = (y * c + z * s, z * c - y * s);
+
+        Vector3 { x, y, z }
+    }
+}
+
+
+/// Imperfections of the simulated sensor, and how its clock advances.
+///
+/// The default is an ideal sensor at 20 °C, whose clock only advances
+/// when `Simulator::advance` is called.
+///
+/// ```
+/// # extern crate lsm303;
+/// # use std::time::Duration;
+/// # use lsm303::units::si;
+/// # use lsm303::common::Vector3;
+/// # use lsm303::simulator::Config;
+/// # fn main() {
+/// let config = Config::new()
+///     .accelerometer_noise_density(220e-6 * 9.80665)
+///     .accelerometer_bias(Vector3 { x: 0.1 * si::MPS2, y: 0.0 * si::MPS2, z: 0.0 * si::MPS2 })
+///     .transaction_time(Duration::from_micros(200))
+///     .seed(42);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    accelerometer_noise_density: f64,
+    accelerometer_bias: AccelerationVector,
+    magnetometer_noise_density: f64,
+    magnetometer_bias: MagneticField,
+    temperature: si::Kelvin<f64>,
+    transaction_time: Duration,
+    seed: u64,
+}
+
+
+impl Config {
+    /// An ideal sensor.
+    pub fn new() -> Config {
+        let zero_acceleration = 0.0 * si::MPS2;
+        let zero_field = 0.0 * si::T;
+        Config {
+            accelerometer_noise_density: 0.0,
+            accelerometer_bias: Vector3 {
+                x: zero_acceleration,
+                y: zero_acceleration,
+                z: zero_acceleration,
+            },
+            magnetometer_noise_density: 0.0,
+            magnetometer_bias: Vector3 {
+                x: zero_field,
+                y: zero_field,
+                z: zero_field,
+            },
+            temperature: 293.15 * si::K,
+            transaction_time: Duration::from_secs(0),
+            seed: 1,
+        }
+    }
+
+    /// Set the noise density of the accelerometer, in (m/s²)/√Hz.
+    ///
+    /// The noise on each sample is scaled by the square root of
+    /// half the data rate.
+    pub fn accelerometer_noise_density(mut self, density: f64) -> Self {
+        self.accelerometer_noise_density = density;
+        self
+    }
+
+    /// Set a constant offset on the output of the accelerometer.
+    pub fn accelerometer_bias(mut self, bias: AccelerationVector) -> Self {
+        self.accelerometer_bias = bias;
+        self
+    }
+
+    /// Set the noise density of the magnetometer, in T/√Hz.
+    pub fn magnetometer_noise_density(mut self, density: f64) -> Self {
+        self.magnetometer_noise_density = density;
+        self
+    }
+
+    /// Set a constant offset on the output of the magnetometer,
+    /// such as from hard iron.
+    pub fn magnetometer_bias(mut self, bias: MagneticField) -> Self {
+        self.magnetometer_bias = bias;
+        self
+    }
+
+    /// Set the temperature reported by the thermometer.
+    pub fn temperature(mut self, temperature: si::Kelvin<f64>) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Advance the clock by this much after every I2C transaction.
+    pub fn transaction_time(mut self, time: Duration) -> Self {
+        self.transaction_time = time;
+        self
+    }
+
+    /// Seed the random number generator used for noise.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+
+/// A simulated LSM303 on an I2C bus.
+pub struct Simulator<T>
+where
+    T: Trajectory,
+{
+    accelerometer: RegisterFile,
+    magnetometer: RegisterFile,
+    trajectory: T,
+    config: Config,
+    random: Random,
+    /// The simulated time, in seconds.
+    time: f64,
+    /// When the next sample is taken, in seconds.
+    next_acceleration: Option<f64>,
+    next_magnetic_field: Option<f64>,
+    fifo: VecDeque<[u8; 6]>,
+}
+
+
+impl<T> Simulator<T>
+where
+    T: Trajectory,
+{
+    /// Create a simulator with every register at its reset value,
+    /// at time zero.
+    ///
+    /// The accelerometer starts powered down, and the magnetometer asleep.
+    pub fn new(trajectory: T, config: Config) -> Simulator<T> {
+        Simulator {
+            accelerometer: RegisterFile::accelerometer(),
+            magnetometer: RegisterFile::magnetometer(),
+            trajectory,
+            random: Random::new(config.seed),
+            config,
+            time: 0.0,
+            next_acceleration: None,
+            next_magnetic_field: None,
+            fifo: VecDeque::with_capacity(FIFO_SIZE),
+        }
+    }
+
+
+    /// The simulated time.
+    pub fn time(&self) -> si::Second<f64> {
+        self.time * si::S
+    }
+
+
+    /// Advance the clock, taking any samples that are due.
+    pub fn advance(&mut self, duration: Duration) {
+        let end = self.time + duration.as_secs_f64();
+
+        loop {
+            let due_acceleration = self.next_acceleration.filter(|&t| t <= end);
+            let due_magnetic_field = self.next_magnetic_field.filter(|&t| t <= end);
+            match (due_acceleration, due_magnetic_field) {
+                (Some(a), Some(m)) if m < a => self.sample_magnetic_field(m),
+                (Some(a), _) => self.sample_acceleration(a),
+                (None, Some(m)) => self.sample_magnetic_field(m),
+                (None, None) => break,
+            }
+        }
+
+        self.time = end;
+    }
+
+
+    /// The current value of an accelerometer register.
+    pub fn accelerometer_register(&self, register: u8) -> u8 {
+        self.accelerometer.registers[register as usize & 0x7F]
+    }
+
+
+    /// The current value of a magnetometer register.
+    pub fn magnetometer_register(&self, register: u8) -> u8 {
+        self.magnetometer.registers[register as usize & 0x7F]
+    }
+
+
+    fn sample_acceleration(&mut self, time: f64) {
+        type R = CtrlReg1A;
+        let ctrl_reg1_a = CtrlReg1A::from_bits_truncate(self.accelerometer_register(CTRL_REG1_A));
+        let ctrl_reg4_a = CtrlReg4A::from_bits_truncate(self.accelerometer_register(CTRL_REG4_A));
+
+        let rate = accelerometer_rate(ctrl_reg1_a);
+        self.next_acceleration = rate.map(|rate| time + 1.0 / rate);
+        let rate = match rate {
+            Some(rate) => rate,
+            None => return,
+        };
+
+        let mode = if ctrl_reg1_a.contains(R::LPen) {
+            OperatingMode::LowPower
+        } else if ctrl_reg4_a.contains(CtrlReg4A::HR) {
+            OperatingMode::HighResolution
+        } else {
+            OperatingMode::Normal
+        };
+        let fs = ctrl_reg4_a & (CtrlReg4A::FS1 | CtrlReg4A::FS0);
+        let (scale, full_scale) = match fs.bits() >> 4 {
+            0 => (Scale::Scale2G, 2.0),
+            1 => (Scale::Scale4G, 4.0),
+            2 => (Scale::Scale8G, 8.0),
+            _ => (Scale::Scale16G, 16.0),
+        };
+        let bits = match mode {
+            OperatingMode::LowPower => 8,
+            OperatingMode::Normal => 10,
+            OperatingMode::HighResolution => 12,
+        };
+
+        let truth = self.trajectory.sample(time * si::S).acceleration;
+        let bias = self.config.accelerometer_bias;
+        let sigma = self.config.accelerometer_noise_density * (rate / 2.0).sqrt();
+        let lsb = value(accelerometer::lsb_size(scale, mode) / si::MPS2);
+        let full_scale = full_scale * 9.80665;
+        let max = f64::from((1 << (bits - 1)) - 1);
+        let min = -max - 1.0;
+
+        let mut counts = |acceleration: si::MeterPerSecond2<f64>, enabled: bool| {
+            if !enabled {
+                return 0;
+            }
+            let value = value(acceleration / si::MPS2) + sigma * self.random.gaussian();
+            // The output saturates at the full scale, or the range of the output.
+            let value = value.clamp(-full_scale, full_scale);
+            let count = round(value / lsb).clamp(min, max) as i16;
+            // The output is left justified.
+            count << (16 - bits)
+        };
+        let x = counts(truth.x + bias.x, ctrl_reg1_a.contains(R::Xen));
+        let y = counts(truth.y + bias.y, ctrl_reg1_a.contains(R::Yen));
+        let z = counts(truth.z + bias.z, ctrl_reg1_a.contains(R::Zen));
+
+        let mut data = [0; 6];
+        data[0..2].copy_from_slice(&x.to_le_bytes());
+        data[2..4].copy_from_slice(&y.to_le_bytes());
+        data[4..6].copy_from_slice(&z.to_le_bytes());
+
+        // New data that arrives before the last was read is an overrun.
+        type S = StatusRegA;
+        let status = &mut self.accelerometer.registers[STATUS_REG_A as usize];
+        let mut flags = S::from_bits_truncate(*status);
+        if flags.contains(S::ZYXDA) {
+            flags.insert(S::ZYXOR | S::ZOR | S::YOR | S::XOR);
+        }
+        flags.insert(S::ZYXDA | S::ZDA | S::YDA | S::XDA);
+        *status = flags.bits();
+
+        match self.fifo_mode() {
+            None => self.set_acceleration_output(data),
+            Some(mode) => {
+                if self.fifo.len() == FIFO_SIZE {
+                    if mode == FifoCtrlRegA::FM0 {
+                        // FIFO mode stops collecting when full.
+                        return;
+                    }
+                    self.fifo.pop_front();
+                }
+                self.fifo.push_back(data);
+                self.update_fifo_source();
+            }
+        }
+    }
+
+
+    fn sample_magnetic_field(&mut self, time: f64) {
+        type R = MrRegM;
+        let mr_reg_m = MrRegM::from_bits_truncate(self.magnetometer_register(MR_REG_M));
+        let cra_reg_m = CraRegM::from_bits_truncate(self.magnetometer_register(CRA_REG_M));
+        let crb_reg_m = self.magnetometer_register(CRB_REG_M);
+
+        let rate = magnetometer_rate(cra_reg_m);
+        if mr_reg_m.contains(R::MD0) {
+            // A single measurement puts the device to sleep afterwards.
+            self.magnetometer.registers[MR_REG_M as usize] = (R::MD1 | R::MD0).bits();
+            self.next_magnetic_field = None;
+        } else {
+            self.next_magnetic_field = Some(time + 1.0 / rate);
+        }
+
+        let gain = match crb_reg_m >> 5 {
+            2 => Gain::Gain_1_9,
+            3 => Gain::Gain_2_5,
+            4 => Gain::Gain_4_0,
+            5 => Gain::Gain_4_7,
+            6 => Gain::Gain_5_6,
+            7 => Gain::Gain_8_1,
+            _ => Gain::Gain_1_3,
+        };
+
+        let truth = self.trajectory.sample(time * si::S).magnetic_field;
+        let bias = self.config.magnetometer_bias;
+        let sigma = self.config.magnetometer_noise_density * (rate / 2.0).sqrt();
+        let (lsb_xy, lsb_z) = magnetometer::lsb_size(gain);
+        let full_scale = magnetometer::full_scale(gain) * gauss();
+
+        let mut counts = |field: si::Tesla<f64>, lsb: si::Tesla<f64>| {
+            let field = value(field / si::T) + sigma * self.random.gaussian();
+            let count = round(field / value(lsb / si::T));
+            // Measurements beyond the full scale of the gain read as -4096.
+            if count.abs() > round(value(full_scale / lsb)) {
+                OVERFLOW
+            } else {
+                count as i16
+            }
+        };
+        let x = counts(truth.x + bias.x, lsb_xy);
+        let y = counts(truth.y + bias.y, lsb_xy);
+        let z = counts(truth.z + bias.z, lsb_z);
+
+        // The registers are ordered X, Z, Y.
+        let registers = &mut self.magnetometer.registers;
+        let start = OUT_X_H_M as usize;
+        registers[start..start + 2].copy_from_slice(&x.to_be_bytes());
+        registers[start + 2..start + 4].copy_from_slice(&z.to_be_bytes());
+        registers[start + 4..start + 6].copy_from_slice(&y.to_be_bytes());
+
+        if cra_reg_m.contains(CraRegM::TEMP_EN) {
+            // 8 LSB/°C, left justified, with zero at 20 °C.
+            let temperature = value((self.config.temperature - 293.15 * si::K) / si::K);
+            let count = round(temperature * 8.0).clamp(-2048.0, 2047.0) as i16;
+            let start = TEMP_OUT_H_M as usize;
+            registers[start..start + 2].copy_from_slice(&(count << 4).to_be_bytes());
+        }
+
+        registers[SR_REG_M as usize] |= SrRegM::DRDY.bits();
+    }
+
+
+    /// The FIFO mode, if the FIFO is enabled and not in bypass mode.
+    fn fifo_mode(&self) -> Option<FifoCtrlRegA> {
+        let ctrl_reg5_a = CtrlReg5A::from_bits_truncate(self.accelerometer_register(CTRL_REG5_A));
+        let fifo_ctrl_reg_a =
+            FifoCtrlRegA::from_bits_truncate(self.accelerometer_register(FIFO_CTRL_REG_A));
+        let mode = fifo_ctrl_reg_a & (FifoCtrlRegA::FM1 | FifoCtrlRegA::FM0);
+
+        if ctrl_reg5_a.contains(CtrlReg5A::FIFO_EN) && !mode.is_empty() {
+            Some(mode)
+        } else {
+            None
+        }
+    }
+
+
+    fn set_acceleration_output(&mut self, data: [u8; 6]) {
+        let start = OUT_X_L_A as usize;
+        self.accelerometer.registers[start..start + 6].copy_from_slice(&data);
+    }
+
+
+    fn update_fifo_source(&mut self) {
+        type R = FifoSrcRegA;
+        let watermark = self.accelerometer_register(FIFO_CTRL_REG_A) & 0x1F;
+        let len = self.fifo.len();
+
+        let mut flags = R::from_bits_truncate(len as u8 & 0x1F);
+        flags.set(R::WTM, len > watermark as usize);
+        flags.set(R::OVRN_FIFO, len == FIFO_SIZE);
+        flags.set(R::EMPTY, len == 0);
+        self.accelerometer.registers[FIFO_SRC_REG_A as usize] = flags.bits();
+    }
+
+
+    fn read_accelerometer(&mut self) -> u8 {
+        let register = self.accelerometer.pointer;
+        let fifo = self.fifo_mode().is_some();
+
+        if register == OUT_X_L_A && fifo {
+            if let Some(data) = self.fifo.pop_front() {
+                self.set_acceleration_output(data);
+                self.update_fifo_source();
+            }
+        }
+        if (OUT_X_L_A..=OUT_Z_H_A).contains(&register) {
+            self.accelerometer.registers[STATUS_REG_A as usize] = 0;
+        }
+
+        let value = self.accelerometer.read();
+
+        // In FIFO mode the address wraps around to the start of the next sample.
+        if fifo && self.accelerometer.increment && self.accelerometer.pointer == OUT_Z_H_A + 1 {
+            self.accelerometer.pointer = OUT_X_L_A;
+        }
+        value
+    }
+
+
+    fn read_magnetometer(&mut self) -> u8 {
+        let register = self.magnetometer.pointer;
+        if (OUT_X_H_M..=OUT_Y_L_M).contains(&register) {
+            self.magnetometer.registers[SR_REG_M as usize] &= !SrRegM::DRDY.bits();
+        }
+        self.magnetometer.read()
+    }
+
+
+    fn write_accelerometer(&mut self, value: u8) {
+        let register = self.accelerometer.pointer;
+        self.accelerometer.write(value);
+
+        match register {
+            CTRL_REG1_A => {
+                let flags = CtrlReg1A::from_bits_truncate(value);
+                let time = self.time;
+                self.next_acceleration = accelerometer_rate(flags).map(|rate| time + 1.0 / rate);
+            }
+            CTRL_REG5_A | FIFO_CTRL_REG_A => {
+                // Leaving FIFO mode empties the FIFO.
+                if self.fifo_mode().is_none() {
+                    self.fifo.clear();
+                }
+                self.update_fifo_source();
+            }
+            _ => {}
+        }
+    }
+
+
+    fn write_magnetometer(&mut self, value: u8) {
+        let register = self.magnetometer.pointer;
+        self.magnetometer.write(value);
+
+        if register == CRA_REG_M || register == MR_REG_M {
+            let mr_reg_m = MrRegM::from_bits_truncate(self.magnetometer_register(MR_REG_M));
+            let cra_reg_m = CraRegM::from_bits_truncate(self.magnetometer_register(CRA_REG_M));
+            self.next_magnetic_field = if mr_reg_m.contains(MrRegM::MD1) {
+                None
+            } else {
+                Some(self.time + 1.0 / magnetometer_rate(cra_reg_m))
+            };
+        }
+    }
+}
+
+
+impl<T> ErrorType for Simulator<T>
+where
+    T: Trajectory,
+{
+    type Error = ErrorKind;
+}
+
+
+impl<T> I2c for Simulator<T>
+where
+    T: Trajectory,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation],
+    ) -> Result<(), ErrorKind> {
+        let accelerometer = match address {
+            ACCELEROMETER_ADDRESS => true,
+            MAGNETOMETER_ADDRESS => false,
+            _ => return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+        };
+
+        let mut selected = false;
+        for operation in operations {
+            match *operation {
+                Operation::Write(bytes) => for &byte in bytes {
+                    match (selected, accelerometer) {
+                        (false, true) => self.accelerometer.select(byte),
+                        (false, false) => self.magnetometer.select(byte),
+                        (true, true) => self.write_accelerometer(byte),
+                        (true, false) => self.write_magnetometer(byte),
+                    }
+                    selected = true;
+                },
+                Operation::Read(ref mut buffer) => for byte in buffer.iter_mut() {
+                    *byte = if accelerometer {
+                        self.read_accelerometer()
+                    } else {
+                        self.read_magnetometer()
+                    };
+                },
+            }
+        }
+
+        let transaction_time = self.config.transaction_time;
+        self.advance(transaction_time);
+        Ok(())
+    }
+}
+
+
+/// The data rate of the accelerometer in Hz, or `None` if it is powered down.
+///
+/// Refer to Table 20 of the datasheet.
+fn accelerometer_rate(flags: CtrlReg1A) -> Option<f64> {
+    let low_power = flags.contains(CtrlReg1A::LPen);
+    let rate = match flags.bits() >> 4 {
+        0 => return None,
+        1 => 1.0,
+        2 => 10.0,
+        3 => 25.0,
+        4 => 50.0,
+        5 => 100.0,
+        6 => 200.0,
+        7 => 400.0,
+        8 => 1620.0,
+        _ if low_power => 5376.0,
+        _ => 1344.0,
+    };
+    Some(rate)
+}
+
+
+/// The data rate of the magnetometer in Hz.
+///
+/// Refer to Table 72 of the datasheet.
+fn magnetometer_rate(flags: CraRegM) -> f64 {
+    match (flags.bits() >> 2) & 0x07 {
+        0 => 0.75,
+        1 => 1.5,
+        2 => 3.0,
+        3 => 7.5,
+        4 => 15.0,
+        5 => 30.0,
+        6 => 75.0,
+        _ => 220.0,
+    }
+}
+
+
+/// A small, seedable random number generator (xorshift64*).
+struct Random {
+    state: u64,
+}
+
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        // The state must not be zero.
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Random { state }
+    }
+
+    /// A uniform sample in (0, 1].
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let bits = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        (bits + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A sample from the standard normal distribution (Box-Muller).
+    fn gaussian(&mut self) -> f64 {
+        let r = (-2.0 * self.uniform().ln()).sqrt();
+        let theta = 2.0 * ::std::f64::consts::PI * self.uniform();
+        r * theta.cos()
+    }
+}
@@ -0,0 +1,79 @@
+//! Units of measurement.
+//!
+//! With the `dimensioned` feature, quantities carry their units in their
+//! types, using [`dimensioned`](https://docs.rs/dimensioned), and `si` is
+//! `dimensioned::si`. The feature is enabled by default, and needs `std`.
+//!
+//! Without it, the `si` module here is a stand-in with the same names,
+//! in which every quantity is a plain `f64` in SI units. Enabling `std`
+//! alone does not change which one is used.
+//! Code that writes quantities as `2.0 * si::MPS2`, reads them back as
+//! `acceleration / si::MPS2`, and takes the number out of that with `value`
+//! works with either.
+
+#[cfg(feature = "dimensioned")]
+pub use dimensioned::si;
+
+
+/// SI units as plain numbers, for use without `std`.
+#[cfg(not(feature = "dimensioned"))]
+pub mod si {
+    /// An acceleration, in metres per second squared.
+    pub type MeterPerSecond2<V> = V;
+    /// A magnetic flux density, in Tesla.
+    pub type Tesla<V> = V;
+    /// A temperature, in Kelvin.
+    pub type Kelvin<V> = V;
+    /// A duration, in seconds.
+    pub type Second<V> = V;
+    /// A frequency, in Hertz.
+    pub type Hertz<V> = V;
+    /// A ratio of two quantities of the same kind.
+    pub type Unitless<V> = V;
+
+    /// One metre per second squared.
+    pub const MPS2: f64 = 1.0;
+    /// One Tesla.
+    pub const T: f64 = 1.0;
+    /// One Kelvin.
+    pub const K: f64 = 1.0;
+    /// One second.
+    pub const S: f64 = 1.0;
+    /// One Hertz.
+    pub const HZ: f64 = 1.0;
+}
+
+
+/// The acceleration of free fall, g.
+pub(crate) fn standard_gravity() -> si::MeterPerSecond2<f64> {
+    9.80665 * si::MPS2
+}
+
+
+/// One Gauss.
+pub(crate) fn gauss() -> si::Tesla<f64> {
+    1e-4 * si::T
+}
+
+
+/// The value of a ratio of two quantities.
+///
+/// ```
+/// # extern crate lsm303;
+/// # use lsm303::units::{si, value};
+/// # fn main() {
+/// let a = 3.0 * si::MPS2;
+/// assert_eq!(value(a / (1.5 * si::MPS2)), 2.0);
+/// # }
+/// ```
+#[cfg(feature = "dimensioned")]
+pub fn value(ratio: si::Unitless<f64>) -> f64 {
+    *ratio
+}
+
+
+/// The value of a ratio of two quantities.
+#[cfg(not(feature = "dimensioned"))]
+pub fn value(ratio: si::Unitless<f64>) -> f64 {
+    ratio
+}
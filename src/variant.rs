@@ -0,0 +1,1005 @@
+//! Support for other members of the LSM303 family.
+//!
+//! `Accelerometer` and `Magnetometer` cover the LSM303DLHC in depth.
+//! The LSM303D, LSM303AGR and LSM303C sit at different addresses, and differ
+//! in their register maps, sensitivities and output formats.
+//! `Driver` works with any of them, given a type that implements `Variant`,
+//! and keeps the `read_acceleration` and `read_magnetic_field` methods.
+//! The LSM303DLHC is a `Variant` as well, for code that handles several parts.
+//!
+//! The register definitions for each variant are in `registers`.
+//! `detect` finds out which variants are on a bus, and at which addresses.
+//!
+//! ```no_run
+//! # use lsm303::variant::{Driver, Lsm303agr};
+//! # fn main() { test().unwrap(); }
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut sensor: Driver<_, Lsm303agr> = Driver::new("/dev/i2c-1")?;
+//! let accel = sensor.read_acceleration()?;
+//! let field = sensor.read_magnetic_field()?;
+//! println!("Accel: ({}, {}, {})  ||  Mag: ({}, {}, {})",
+//!          accel.x, accel.y, accel.z,
+//!          field.x, field.y, field.z);
+//! # Ok(())
+//! # }
+//! ```
+
+use accelerometer::{self, AccelerationVector, OperatingMode, Scale};
+use common::Vector3;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut, Mul};
+use embedded_hal::i2c::I2c;
+use errors::{Error, Result, Sensor};
+#[cfg(feature = "linux")]
+use linux_embedded_hal::{I2CError, I2cdev};
+use magnetometer::{Gain, MagneticField, RawMagneticField};
+use registers;
+use units::{gauss, si, standard_gravity};
+
+
+/// Setting the MSB of the register address enables auto-increment.
+const AUTO_INCREMENT: u8 = 0x80;
+
+
+/// The I2C addresses of the accelerometer and magnetometer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Addresses {
+    /// The address of the accelerometer.
+    pub accelerometer: u8,
+    /// The address of the magnetometer.
+    pub magnetometer: u8,
+}
+
+
+/// The parts of the driver that differ between variants of the LSM303.
+///
+/// Each method is given the bus, and the addresses of the sensors on it.
+pub trait Variant {
+    /// The addresses of the sensors, with any address pins at their default level.
+    const ADDRESSES: Addresses;
+
+    /// Check the identification registers.
+    ///
+    /// Returns `Error::WrongDevice` if they do not match the variant.
+    fn identify<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c;
+
+    /// Enable all axes of both sensors, and the temperature sensor.
+    ///
+    /// The accelerometer is set to +/- 2G.
+    fn initialize<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c;
+
+    /// Set the scale of the acceleration measurement.
+    ///
+    /// Returns `Error::ScaleNotAvailable` if the variant does not support it.
+    fn set_scale<Dev>(device: &mut Dev, addresses: Addresses, scale: Scale) -> Result<(), Dev::Error>
+    where
+        Dev: I2c;
+
+    /// Read the accelerometer, which is set to `scale`.
+    fn read_acceleration<Dev>(
+        device: &mut Dev,
+        addresses: Addresses,
+        scale: Scale,
+    ) -> Result<AccelerationVector, Dev::Error>
+    where
+        Dev: I2c;
+
+    /// Read the magnetometer.
+    fn read_magnetic_field<Dev>(device: &mut Dev, addresses: Addresses) -> Result<MagneticField, Dev::Error>
+    where
+        Dev: I2c;
+
+    /// Read the temperature sensor.
+    fn read_temperature<Dev>(device: &mut Dev, addresses: Addresses) -> Result<si::Kelvin<f64>, Dev::Error>
+    where
+        Dev: I2c;
+}
+
+
+/// The LSM303DLHC, which `Accelerometer` and `Magnetometer` support in full.
+///
+/// The magnetometer is set to +/- 1.3 Gauss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lsm303dlhc;
+
+
+/// The LSM303D, which has both sensors at a single address.
+///
+/// The default address assumes that SA0 is high;
+/// with SA0 low, both sensors are at `0x1E`.
+/// The magnetometer is set to +/- 4 Gauss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lsm303d;
+
+
+/// The LSM303AGR.
+///
+/// The magnetometer has a fixed range of +/- 50 Gauss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lsm303agr;
+
+
+/// The LSM303C.
+///
+/// The accelerometer does not support +/- 16G.
+/// The magnetometer is set to +/- 16 Gauss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lsm303c;
+
+
+/// Interface to any variant of the LSM303.
+pub struct Driver<Dev, V>
+where
+    Dev: I2c,
+    V: Variant,
+{
+    device: Dev,
+    addresses: Addresses,
+    scale: Scale,
+    variant: PhantomData<V>,
+}
+
+
+/// The variants of the LSM303 that `probe` can find.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    /// Use `Lsm303dlhc`, or `Accelerometer` and `Magnetometer`.
+    Lsm303dlhc,
+    /// Use `Lsm303d`.
+    Lsm303d,
+    /// Use `Lsm303agr`.
+    Lsm303agr,
+    /// Use `Lsm303c`.
+    Lsm303c,
+}
+
+
+/// A variant of the LSM303 found on a bus.
+///
+/// Pass `addresses` to `Driver::from_i2c_device_at`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Detected {
+    /// Which variant was found.
+    pub model: Model,
+    /// The addresses of its sensors.
+    pub addresses: Addresses,
+}
+
+
+#[cfg(feature = "linux")]
+impl<V> Driver<I2cdev, V>
+where
+    V: Variant,
+{
+    /// Initialize both sensors for a Linux I2C device.
+    ///
+    /// ```
+    /// # use lsm303::variant::{Driver, Lsm303d};
+    /// let sensor = Driver::<_, Lsm303d>::new("/dev/i2c-1");
+    /// ```
+    pub fn new<Path>(path: Path) -> Result<Driver<I2cdev, V>, I2CError>
+    where
+        Path: AsRef<::std::path::Path>,
+    {
+        let device = I2cdev::new(&path).map_err(|e| Error::FailedToOpenDevice(e.into()))?;
+
+        Driver::from_i2c_device(device)
+    }
+}
+
+
+impl<Dev, V> Driver<Dev, V>
+where
+    Dev: I2c,
+    V: Variant,
+{
+    /// Initialize both sensors at their default addresses, given an open I2C device.
+    ///
+    /// The identification registers are checked first, and
+    /// `Error::WrongDevice` is returned if they do not match.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::variant::{Driver, Lsm303c};
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// let device = I2cdev::new("/dev/i2c-1")?;
+    /// let sensor: Driver<_, Lsm303c> = Driver::from_i2c_device(device)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_i2c_device(device: Dev) -> Result<Driver<Dev, V>, Dev::Error> {
+        Driver::from_i2c_device_at(device, V::ADDRESSES)
+    }
+
+    /// Initialize both sensors at the given addresses.
+    ///
+    /// ```no_run
+    /// # extern crate lsm303;
+    /// # use lsm303::variant::{Addresses, Driver, Lsm303d};
+    /// # extern crate linux_embedded_hal;
+    /// # use linux_embedded_hal::I2cdev;
+    /// # fn main() { test().unwrap(); }
+    /// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+    /// // SA0 is tied low.
+    /// let addresses = Addresses { accelerometer: 0x1E, magnetometer: 0x1E };
+    /// let device = I2cdev::new("/dev/i2c-1")?;
+    /// let sensor: Driver<_, Lsm303d> = Driver::from_i2c_device_at(device, addresses)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_i2c_device_at(mut device: Dev, addresses: Addresses) -> Result<Driver<Dev, V>, Dev::Error> {
+        V::identify(&mut device, addresses)?;
+        V::initialize(&mut device, addresses)?;
+
+        let driver = Driver {
+            device,
+            addresses,
+            scale: Scale::Scale2G,
+            variant: PhantomData,
+        };
+        Ok(driver)
+    }
+
+    /// The addresses of the sensors.
+    pub fn addresses(&self) -> Addresses {
+        self.addresses
+    }
+
+    /// Read the accelerometer, returning a vector of accelerations.
+    pub fn read_acceleration(&mut self) -> Result<AccelerationVector, Dev::Error> {
+        V::read_acceleration(&mut self.device, self.addresses, self.scale)
+    }
+
+    /// Read the magnetometer, returning the magnetic field as a vector.
+    pub fn read_magnetic_field(&mut self) -> Result<MagneticField, Dev::Error> {
+        V::read_magnetic_field(&mut self.device, self.addresses)
+    }
+
+    /// Read the temperature sensor.
+    ///
+    /// None of the variants calibrate the absolute temperature,
+    /// so treat it as relative.
+    pub fn read_temperature(&mut self) -> Result<si::Kelvin<f64>, Dev::Error> {
+        V::read_temperature(&mut self.device, self.addresses)
+    }
+
+    /// Set the scale of the acceleration measurement.
+    ///
+    /// Returns `Error::ScaleNotAvailable` if the variant does not support it.
+    pub fn set_scale(&mut self, scale: Scale) -> Result<(), Dev::Error> {
+        V::set_scale(&mut self.device, self.addresses, scale)?;
+        self.scale = scale;
+        Ok(())
+    }
+
+    /// The current scale of the acceleration measurement.
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+}
+
+
+/// Access the underlying I2C bus.
+///
+/// Most of the methods require a mutable reference; `DerefMut` is implemented as well.
+impl<Dev, V> Deref for Driver<Dev, V>
+where
+    Dev: I2c,
+    V: Variant,
+{
+    type Target = Dev;
+
+    fn deref(&self) -> &Dev {
+        &self.device
+    }
+}
+
+
+/// Access the underlying I2C bus.
+///
+/// Refer to the datasheet of the variant if you plan on accessing the device directly.
+impl<Dev, V> DerefMut for Driver<Dev, V>
+where
+    Dev: I2c,
+    V: Variant,
+{
+    fn deref_mut(&mut self) -> &mut Dev {
+        &mut self.device
+    }
+}
+
+
+/// Find the variants of the LSM303 on a Linux I2C bus.
+///
+/// See `probe` for details.
+///
+/// ```no_run
+/// # extern crate lsm303;
+/// # extern crate linux_embedded_hal;
+/// # use linux_embedded_hal::I2cdev;
+/// # use lsm303::variant::{detect, Driver, Lsm303agr, Model};
+/// # fn main() { test().unwrap(); }
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// for found in detect("/dev/i2c-1")? {
+///     if found.model == Model::Lsm303agr {
+///         let device = I2cdev::new("/dev/i2c-1")?;
+///         let mut sensor: Driver<_, Lsm303agr> =
+///             Driver::from_i2c_device_at(device, found.addresses)?;
+///         println!("Acceleration: {:?}", sensor.read_acceleration()?);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "linux")]
+pub fn detect<Path>(path: Path) -> Result<Vec<Detected>, I2CError>
+where
+    Path: AsRef<::std::path::Path>,
+{
+    let mut device = I2cdev::new(&path).map_err(|e| Error::FailedToOpenDevice(e.into()))?;
+
+    Ok(probe(&mut device))
+}
+
+
+/// Find the variants of the LSM303 on an I2C bus.
+///
+/// Every address used by the family is probed, and the identification
+/// registers are compared with each variant: `IRA_REG_M` to `IRC_REG_M`
+/// on the LSM303DLHC, and `WHO_AM_I` on the others.
+/// A variant is reported if all of its identification registers match.
+/// Addresses that do not respond are skipped.
+///
+/// Only registers are read; none are written.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature = "std")]
+pub fn probe<Dev>(device: &mut Dev) -> Vec<Detected>
+where
+    Dev: I2c,
+{
+    use registers::{lsm303agr, lsm303c, lsm303d};
+
+    let mut dlhc_id = [0; 3];
+    let dlhc = device
+        .write_read(0x1E, &[registers::IRA_REG_M], &mut dlhc_id)
+        .is_ok() && &dlhc_id == b"H43";
+    let agr_magnetometer = read_id(device, 0x1E, lsm303agr::WHO_AM_I_M);
+    let magnetometer = read_id(device, 0x1E, lsm303c::WHO_AM_I_M);
+    let agr_accelerometer = read_id(device, 0x19, lsm303agr::WHO_AM_I_A);
+    let accelerometer = read_id(device, 0x1D, lsm303c::WHO_AM_I_A);
+
+    let mut found = Vec::new();
+    if dlhc {
+        found.push(Detected {
+            model: Model::Lsm303dlhc,
+            addresses: Lsm303dlhc::ADDRESSES,
+        });
+    }
+    if agr_accelerometer == Some(lsm303agr::WHO_AM_I_A_VALUE) &&
+        agr_magnetometer == Some(lsm303agr::WHO_AM_I_M_VALUE)
+    {
+        found.push(Detected {
+            model: Model::Lsm303agr,
+            addresses: Lsm303agr::ADDRESSES,
+        });
+    }
+    if accelerometer == Some(lsm303c::WHO_AM_I_A_VALUE) &&
+        magnetometer == Some(lsm303c::WHO_AM_I_M_VALUE)
+    {
+        found.push(Detected {
+            model: Model::Lsm303c,
+            addresses: Lsm303c::ADDRESSES,
+        });
+    }
+    // The LSM303D is at 0x1D with SA0 high, or 0x1E with SA0 low.
+    for &(id, address) in &[(accelerometer, 0x1D), (magnetometer, 0x1E)] {
+        if id == Some(lsm303d::WHO_AM_I_VALUE) {
+            found.push(Detected {
+                model: Model::Lsm303d,
+                addresses: Addresses {
+                    accelerometer: address,
+                    magnetometer: address,
+                },
+            });
+        }
+    }
+    found
+}
+
+
+impl Variant for Lsm303dlhc {
+    const ADDRESSES: Addresses = Addresses {
+        accelerometer: 0x32 >> 1,
+        magnetometer: 0x3C >> 1,
+    };
+
+    fn identify<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        // The accelerometer has no identification register.
+        let address = addresses.magnetometer;
+        check_id(device, address, Sensor::Magnetometer, registers::IRA_REG_M, b"H43")
+    }
+
+    fn initialize<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::*;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        // 10 Hz, all axes enabled, high resolution.
+        let ctrl_reg1_a = CtrlReg1A::ODR1 | CtrlReg1A::Zen | CtrlReg1A::Yen | CtrlReg1A::Xen;
+        write_register!(device, address, sensor, CTRL_REG1_A, ctrl_reg1_a)?;
+        let ctrl_reg4_a = CtrlReg4A::HR;
+        write_register!(device, address, sensor, CTRL_REG4_A, ctrl_reg4_a)?;
+
+        let address = addresses.magnetometer;
+        let sensor = Sensor::Magnetometer;
+
+        // Continuous, temperature enabled, 15 Hz, +/- 1.3 Gauss.
+        let mr_reg_m = MrRegM::empty();
+        write_register!(device, address, sensor, MR_REG_M, mr_reg_m)?;
+        let cra_reg_m = CraRegM::TEMP_EN | CraRegM::DO2;
+        write_register!(device, address, sensor, CRA_REG_M, cra_reg_m)?;
+        let crb_reg_m = CrbRegM::GN0;
+        write_register!(device, address, sensor, CRB_REG_M, crb_reg_m)
+    }
+
+    fn set_scale<Dev>(device: &mut Dev, addresses: Addresses, scale: Scale) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::{CTRL_REG4_A, CtrlReg4A};
+        type R = CtrlReg4A;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        let mut flags = read_register!(device, address, sensor, CTRL_REG4_A, CtrlReg4A)?;
+        flags.remove(R::FS1 | R::FS0);
+        flags.insert(match scale {
+            Scale::Scale2G => R::empty(),
+            Scale::Scale4G => R::FS0,
+            Scale::Scale8G => R::FS1,
+            Scale::Scale16G => R::FS1 | R::FS0,
+        });
+        write_register!(device, address, sensor, CTRL_REG4_A, flags)
+    }
+
+    fn read_acceleration<Dev>(
+        device: &mut Dev,
+        addresses: Addresses,
+        scale: Scale,
+    ) -> Result<AccelerationVector, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        let mut data = [0; 6];
+        read(
+            device,
+            addresses.accelerometer,
+            Sensor::Accelerometer,
+            registers::OUT_X_L_A,
+            AUTO_INCREMENT,
+            &mut data,
+        )?;
+
+        // 12 bit output, left justified.
+        let lsb = accelerometer::lsb_size(scale, OperatingMode::HighResolution);
+        Ok(scale_counts(shift(little_endian(&data), 4), lsb))
+    }
+
+    fn read_magnetic_field<Dev>(device: &mut Dev, addresses: Addresses) -> Result<MagneticField, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let mut data = [0; 6];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, registers::OUT_X_H_M, 0, &mut data)?;
+
+        // Big endian, and ordered as X, Z, Y.
+        let raw = RawMagneticField {
+            counts: Vector3 {
+                x: BigEndian::read_i16(&data[0..2]),
+                y: BigEndian::read_i16(&data[4..6]),
+                z: BigEndian::read_i16(&data[2..4]),
+            },
+            gain: Gain::Gain_1_3,
+        };
+        Ok(raw.to_magnetic_field()?)
+    }
+
+    fn read_temperature<Dev>(device: &mut Dev, addresses: Addresses) -> Result<si::Kelvin<f64>, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let mut data = [0; 2];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, registers::TEMP_OUT_H_M, 0, &mut data)?;
+
+        // 12 bits, left justified, at 8 LSB/°C.
+        // Zero is taken as 20 °C, the same default as `Magnetometer`.
+        let raw = BigEndian::read_i16(&data) >> 4;
+        Ok(celsius(20.0 + raw as f64 / 8.0))
+    }
+}
+
+
+impl Variant for Lsm303d {
+    const ADDRESSES: Addresses = Addresses {
+        accelerometer: 0x1D,
+        magnetometer: 0x1D,
+    };
+
+    fn identify<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303d::{WHO_AM_I, WHO_AM_I_VALUE};
+
+        check_id(device, addresses.accelerometer, Sensor::Accelerometer, WHO_AM_I, &[WHO_AM_I_VALUE])
+    }
+
+    fn initialize<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303d::*;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        // 50 Hz, all axes enabled, +/- 2G.
+        let ctrl1 = Ctrl1::AODR2 | Ctrl1::AODR0 | Ctrl1::AZEN | Ctrl1::AYEN | Ctrl1::AXEN;
+        write_register!(device, address, sensor, CTRL1, ctrl1)?;
+        let ctrl2 = Ctrl2::empty();
+        write_register!(device, address, sensor, CTRL2, ctrl2)?;
+
+        let address = addresses.magnetometer;
+        let sensor = Sensor::Magnetometer;
+
+        // Temperature enabled, high resolution, 50 Hz, +/- 4 Gauss, continuous.
+        let ctrl5 = Ctrl5::TEMP_EN | Ctrl5::M_RES1 | Ctrl5::M_RES0 | Ctrl5::M_ODR2;
+        write_register!(device, address, sensor, CTRL5, ctrl5)?;
+        let ctrl6 = Ctrl6::MFS0;
+        write_register!(device, address, sensor, CTRL6, ctrl6)?;
+        let ctrl7 = Ctrl7::empty();
+        write_register!(device, address, sensor, CTRL7, ctrl7)
+    }
+
+    fn set_scale<Dev>(device: &mut Dev, addresses: Addresses, scale: Scale) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303d::{CTRL2, Ctrl2};
+        type R = Ctrl2;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        // +/- 6G is not supported, since `Scale` has no equivalent.
+        let mut flags = read_register!(device, address, sensor, CTRL2, Ctrl2)?;
+        flags.remove(R::AFS2 | R::AFS1 | R::AFS0);
+        flags.insert(match scale {
+            Scale::Scale2G => R::empty(),
+            Scale::Scale4G => R::AFS0,
+            Scale::Scale8G => R::AFS1 | R::AFS0,
+            Scale::Scale16G => R::AFS2,
+        });
+        write_register!(device, address, sensor, CTRL2, flags)
+    }
+
+    fn read_acceleration<Dev>(
+        device: &mut Dev,
+        addresses: Addresses,
+        scale: Scale,
+    ) -> Result<AccelerationVector, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303d::OUT_X_L_A;
+
+        let mut data = [0; 6];
+        read(device, addresses.accelerometer, Sensor::Accelerometer, OUT_X_L_A, AUTO_INCREMENT, &mut data)?;
+
+        // Table 3 of the LSM303D datasheet, in mg/LSB.
+        let lsb = milli_g(match scale {
+            Scale::Scale2G => 0.061,
+            Scale::Scale4G => 0.122,
+            Scale::Scale8G => 0.244,
+            Scale::Scale16G => 0.732,
+        });
+        Ok(scale_counts(little_endian(&data), lsb))
+    }
+
+    fn read_magnetic_field<Dev>(device: &mut Dev, addresses: Addresses) -> Result<MagneticField, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303d::OUT_X_L_M;
+
+        let mut data = [0; 6];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, OUT_X_L_M, AUTO_INCREMENT, &mut data)?;
+
+        // Table 3 of the LSM303D datasheet, at +/- 4 Gauss.
+        Ok(scale_counts(little_endian(&data), milli_gauss(0.160)))
+    }
+
+    fn read_temperature<Dev>(device: &mut Dev, addresses: Addresses) -> Result<si::Kelvin<f64>, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use byteorder::{ByteOrder, LittleEndian};
+        use registers::lsm303d::TEMP_OUT_L;
+
+        let mut data = [0; 2];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, TEMP_OUT_L, AUTO_INCREMENT, &mut data)?;
+
+        // 12 bits, right justified, at 8 LSB/°C. Zero is taken as 25 °C.
+        let raw = (LittleEndian::read_i16(&data) << 4) >> 4;
+        Ok(celsius(25.0 + raw as f64 / 8.0))
+    }
+}
+
+
+impl Variant for Lsm303agr {
+    const ADDRESSES: Addresses = Addresses {
+        accelerometer: 0x19,
+        magnetometer: 0x1E,
+    };
+
+    fn identify<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303agr::*;
+
+        let address = addresses.accelerometer;
+        check_id(device, address, Sensor::Accelerometer, WHO_AM_I_A, &[WHO_AM_I_A_VALUE])?;
+        let address = addresses.magnetometer;
+        check_id(device, address, Sensor::Magnetometer, WHO_AM_I_M, &[WHO_AM_I_M_VALUE])
+    }
+
+    fn initialize<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303agr::*;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        // 10 Hz, all axes enabled, high resolution, +/- 2G.
+        let ctrl_reg1_a = CtrlReg1A::ODR1 | CtrlReg1A::Zen | CtrlReg1A::Yen | CtrlReg1A::Xen;
+        write_register!(device, address, sensor, CTRL_REG1_A, ctrl_reg1_a)?;
+        // The temperature sensor requires block data update.
+        let ctrl_reg4_a = CtrlReg4A::BDU | CtrlReg4A::HR;
+        write_register!(device, address, sensor, CTRL_REG4_A, ctrl_reg4_a)?;
+        let temp_cfg_reg_a = TempCfgRegA::TEMP_EN1 | TempCfgRegA::TEMP_EN0;
+        write_register!(device, address, sensor, TEMP_CFG_REG_A, temp_cfg_reg_a)?;
+
+        let address = addresses.magnetometer;
+        let sensor = Sensor::Magnetometer;
+
+        // Temperature compensation is required; 10 Hz, continuous.
+        let cfg_reg_a_m = CfgRegAM::COMP_TEMP_EN;
+        write_register!(device, address, sensor, CFG_REG_A_M, cfg_reg_a_m)?;
+        let cfg_reg_c_m = CfgRegCM::BDU;
+        write_register!(device, address, sensor, CFG_REG_C_M, cfg_reg_c_m)
+    }
+
+    fn set_scale<Dev>(device: &mut Dev, addresses: Addresses, scale: Scale) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303agr::{CTRL_REG4_A, CtrlReg4A};
+        type R = CtrlReg4A;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        let mut flags = read_register!(device, address, sensor, CTRL_REG4_A, CtrlReg4A)?;
+        flags.remove(R::FS1 | R::FS0);
+        flags.insert(match scale {
+            Scale::Scale2G => R::empty(),
+            Scale::Scale4G => R::FS0,
+            Scale::Scale8G => R::FS1,
+            Scale::Scale16G => R::FS1 | R::FS0,
+        });
+        write_register!(device, address, sensor, CTRL_REG4_A, flags)
+    }
+
+    fn read_acceleration<Dev>(
+        device: &mut Dev,
+        addresses: Addresses,
+        scale: Scale,
+    ) -> Result<AccelerationVector, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303agr::OUT_X_L_A;
+
+        let mut data = [0; 6];
+        read(device, addresses.accelerometer, Sensor::Accelerometer, OUT_X_L_A, AUTO_INCREMENT, &mut data)?;
+
+        // 12 bit output, left justified.
+        // Table 3 of the LSM303AGR datasheet, in mg/digit.
+        let lsb = milli_g(match scale {
+            Scale::Scale2G => 0.98,
+            Scale::Scale4G => 1.95,
+            Scale::Scale8G => 3.9,
+            Scale::Scale16G => 11.72,
+        });
+        Ok(scale_counts(shift(little_endian(&data), 4), lsb))
+    }
+
+    fn read_magnetic_field<Dev>(device: &mut Dev, addresses: Addresses) -> Result<MagneticField, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303agr::OUTX_L_REG_M;
+
+        let mut data = [0; 6];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, OUTX_L_REG_M, AUTO_INCREMENT, &mut data)?;
+
+        // Table 4 of the LSM303AGR datasheet.
+        Ok(scale_counts(little_endian(&data), milli_gauss(1.5)))
+    }
+
+    fn read_temperature<Dev>(device: &mut Dev, addresses: Addresses) -> Result<si::Kelvin<f64>, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use byteorder::{ByteOrder, LittleEndian};
+        use registers::lsm303agr::OUT_TEMP_L_A;
+
+        let mut data = [0; 2];
+        read(device, addresses.accelerometer, Sensor::Accelerometer, OUT_TEMP_L_A, AUTO_INCREMENT, &mut data)?;
+
+        // Left justified, at 1 digit/°C in the high byte. Zero is 25 °C.
+        let raw = LittleEndian::read_i16(&data);
+        Ok(celsius(25.0 + raw as f64 / 256.0))
+    }
+}
+
+
+impl Variant for Lsm303c {
+    const ADDRESSES: Addresses = Addresses {
+        accelerometer: 0x1D,
+        magnetometer: 0x1E,
+    };
+
+    fn identify<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303c::*;
+
+        let address = addresses.accelerometer;
+        check_id(device, address, Sensor::Accelerometer, WHO_AM_I_A, &[WHO_AM_I_A_VALUE])?;
+        let address = addresses.magnetometer;
+        check_id(device, address, Sensor::Magnetometer, WHO_AM_I_M, &[WHO_AM_I_M_VALUE])
+    }
+
+    fn initialize<Dev>(device: &mut Dev, addresses: Addresses) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303c::*;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        // 10 Hz, block data update, all axes enabled, +/- 2G.
+        type A = CtrlReg1A;
+        let ctrl_reg1_a = A::ODR0 | A::BDU | A::Zen | A::Yen | A::Xen;
+        write_register!(device, address, sensor, CTRL_REG1_A, ctrl_reg1_a)?;
+        // The accelerometer auto-increments without the MSB of the address.
+        let ctrl_reg4_a = CtrlReg4A::IF_ADD_INC;
+        write_register!(device, address, sensor, CTRL_REG4_A, ctrl_reg4_a)?;
+
+        let address = addresses.magnetometer;
+        let sensor = Sensor::Magnetometer;
+
+        // Temperature enabled, ultra-high performance, 10 Hz,
+        // +/- 16 Gauss, continuous, block data update.
+        type M = CtrlReg1M;
+        let ctrl_reg1_m = M::TEMP_EN | M::OM1 | M::OM0 | M::DO2;
+        write_register!(device, address, sensor, CTRL_REG1_M, ctrl_reg1_m)?;
+        let ctrl_reg2_m = CtrlReg2M::FS1 | CtrlReg2M::FS0;
+        write_register!(device, address, sensor, CTRL_REG2_M, ctrl_reg2_m)?;
+        let ctrl_reg3_m = CtrlReg3M::empty();
+        write_register!(device, address, sensor, CTRL_REG3_M, ctrl_reg3_m)?;
+        let ctrl_reg4_m = CtrlReg4M::OMZ1 | CtrlReg4M::OMZ0;
+        write_register!(device, address, sensor, CTRL_REG4_M, ctrl_reg4_m)?;
+        let ctrl_reg5_m = CtrlReg5M::BDU;
+        write_register!(device, address, sensor, CTRL_REG5_M, ctrl_reg5_m)
+    }
+
+    fn set_scale<Dev>(device: &mut Dev, addresses: Addresses, scale: Scale) -> Result<(), Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303c::{CTRL_REG4_A, CtrlReg4A};
+        type R = CtrlReg4A;
+
+        let address = addresses.accelerometer;
+        let sensor = Sensor::Accelerometer;
+
+        let setting = match scale {
+            Scale::Scale2G => R::empty(),
+            Scale::Scale4G => R::FS1,
+            Scale::Scale8G => R::FS1 | R::FS0,
+            Scale::Scale16G => return Err(Error::ScaleNotAvailable),
+        };
+        let mut flags = read_register!(device, address, sensor, CTRL_REG4_A, CtrlReg4A)?;
+        flags.remove(R::FS1 | R::FS0);
+        flags.insert(setting);
+        write_register!(device, address, sensor, CTRL_REG4_A, flags)
+    }
+
+    fn read_acceleration<Dev>(
+        device: &mut Dev,
+        addresses: Addresses,
+        scale: Scale,
+    ) -> Result<AccelerationVector, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303c::OUT_X_L_A;
+
+        let mut data = [0; 6];
+        read(device, addresses.accelerometer, Sensor::Accelerometer, OUT_X_L_A, 0, &mut data)?;
+
+        // Table 3 of the LSM303C datasheet, in mg/LSB.
+        let lsb = milli_g(match scale {
+            Scale::Scale2G => 0.061,
+            Scale::Scale4G => 0.122,
+            Scale::Scale8G => 0.244,
+            Scale::Scale16G => return Err(Error::ScaleNotAvailable),
+        });
+        Ok(scale_counts(little_endian(&data), lsb))
+    }
+
+    fn read_magnetic_field<Dev>(device: &mut Dev, addresses: Addresses) -> Result<MagneticField, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use registers::lsm303c::OUT_X_L_M;
+
+        let mut data = [0; 6];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, OUT_X_L_M, AUTO_INCREMENT, &mut data)?;
+
+        // Table 3 of the LSM303C datasheet.
+        Ok(scale_counts(little_endian(&data), milli_gauss(0.58)))
+    }
+
+    fn read_temperature<Dev>(device: &mut Dev, addresses: Addresses) -> Result<si::Kelvin<f64>, Dev::Error>
+    where
+        Dev: I2c,
+    {
+        use byteorder::{ByteOrder, LittleEndian};
+        use registers::lsm303c::TEMP_L_M;
+
+        let mut data = [0; 2];
+        read(device, addresses.magnetometer, Sensor::Magnetometer, TEMP_L_M, AUTO_INCREMENT, &mut data)?;
+
+        // 8 LSB/°C. Zero is taken as 25 °C.
+        let raw = LittleEndian::read_i16(&data);
+        Ok(celsius(25.0 + raw as f64 / 8.0))
+    }
+}
+
+
+/// Read consecutive registers, starting at `register`.
+fn read<Dev>(
+    device: &mut Dev,
+    address: u8,
+    sensor: Sensor,
+    register: u8,
+    auto_increment: u8,
+    data: &mut [u8],
+) -> Result<(), Dev::Error>
+where
+    Dev: I2c,
+{
+    device
+        .write_read(address, &[register | auto_increment], data)
+        .map_err(|e| Error::read(sensor, register, e))
+}
+
+
+/// Read a single identification register, if the device responds.
+#[cfg(feature = "std")]
+fn read_id<Dev>(device: &mut Dev, address: u8, register: u8) -> Option<u8>
+where
+    Dev: I2c,
+{
+    let mut id = [0];
+    device.write_read(address, &[register], &mut id).ok().map(|()| id[0])
+}
+
+
+/// Compare identification registers, starting at `register`.
+fn check_id<Dev>(
+    device: &mut Dev,
+    address: u8,
+    sensor: Sensor,
+    register: u8,
+    expected: &[u8],
+) -> Result<(), Dev::Error>
+where
+    Dev: I2c,
+{
+    let mut id = [0; 3];
+    read(device, address, sensor, register, 0, &mut id[..expected.len()])?;
+    if &id[..expected.len()] != expected {
+        return Err(Error::WrongDevice(id));
+    }
+    Ok(())
+}
+
+
+/// Decode X, Y and Z as little endian 16 bit values.
+fn little_endian(data: &[u8; 6]) -> Vector3<i16> {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    Vector3 {
+        x: LittleEndian::read_i16(&data[0..2]),
+        y: LittleEndian::read_i16(&data[2..4]),
+        z: LittleEndian::read_i16(&data[4..6]),
+    }
+}
+
+
+/// Drop the unused low bits of left justified output.
+fn shift(counts: Vector3<i16>, bits: u32) -> Vector3<i16> {
+    Vector3 {
+        x: counts.x >> bits,
+        y: counts.y >> bits,
+        z: counts.z >> bits,
+    }
+}
+
+
+/// Multiply each count by the size of the LSB.
+fn scale_counts<T>(counts: Vector3<i16>, lsb: T) -> Vector3<T>
+where
+    T: Copy,
+    f64: Mul<T, Output = T>,
+{
+    Vector3 {
+        x: counts.x as f64 * lsb,
+        y: counts.y as f64 * lsb,
+        z: counts.z as f64 * lsb,
+    }
+}
+
+
+fn milli_g(value: f64) -> si::MeterPerSecond2<f64> {
+    1e-3 * value * standard_gravity()
+}
+
+
+fn milli_gauss(value: f64) -> si::Tesla<f64> {
+    1e-3 * value * gauss()
+}
+
+
+fn celsius(value: f64) -> si::Kelvin<f64> {
+    (value + 273.15) * si::K
+}
@@ -0,0 +1,130 @@
+//! Read both sensors over one bus, using the simulator.
+
+extern crate lsm303;
+
+use lsm303::accelerometer::{AccelerationVector, OperatingMode, Rate, Scale};
+use lsm303::combined::Config;
+use lsm303::common::Vector3;
+use lsm303::magnetometer::{Gain, MagneticField};
+use lsm303::registers::CTRL_REG1_A;
+use lsm303::simulator::{self, Orientation, Simulator};
+use lsm303::units::si;
+use lsm303::{Error, Lsm303};
+use std::thread;
+use std::time::Duration;
+
+
+const G: f64 = 9.80665;
+
+
+fn still() -> AccelerationVector {
+    let zero = 0.0 * si::MPS2;
+    Vector3 { x: zero, y: zero, z: zero }
+}
+
+
+fn field(x: f64) -> MagneticField {
+    Vector3 {
+        x: x * si::T,
+        y: 0.0 * si::T,
+        z: -40e-6 * si::T,
+    }
+}
+
+
+#[test]
+fn read_all() {
+    let trajectory = |_| Orientation::default().sample(still(), field(20e-6));
+    let config = simulator::Config::new().temperature(303.15 * si::K);
+    let mut sensor = Lsm303::from_i2c_device(Simulator::new(trajectory, config)).unwrap();
+    sensor.bus().advance(Duration::from_millis(100));
+
+    let first = sensor.read_all().unwrap();
+    assert!((first.acceleration.z / si::MPS2 - G).abs() < 0.01);
+    assert!((first.magnetic_field.x / si::T - 20e-6).abs() < 1e-6);
+    assert!((first.magnetic_field.z / si::T + 40e-6).abs() < 1e-6);
+    assert!((first.temperature / si::K - 303.15).abs() < 0.2);
+
+    let second = sensor.read_all().unwrap();
+    assert!(second.timestamp >= first.timestamp);
+}
+
+
+#[test]
+fn configure() {
+    // 4 g and 2 Gauss are beyond the default scale and gain.
+    let trajectory = |_| {
+        let mut acceleration = still();
+        acceleration.x = 4.0 * G * si::MPS2;
+        Orientation::default().sample(acceleration, field(200e-6))
+    };
+    let simulator = Simulator::new(trajectory, simulator::Config::new());
+    let mut sensor = Lsm303::from_i2c_device(simulator).unwrap();
+    sensor.bus().advance(Duration::from_millis(100));
+
+    match sensor.read_all() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert!(overflow.x),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let config = Config::new().scale(Scale::Scale8G).gain(Gain::Gain_2_5);
+    sensor.configure(config).unwrap();
+    assert_eq!(sensor.accelerometer().scale(), Scale::Scale8G);
+    assert_eq!(sensor.magnetometer().gain(), Gain::Gain_2_5);
+    sensor.bus().advance(Duration::from_millis(100));
+
+    let sample = sensor.read_all().unwrap();
+    assert!((sample.acceleration.x / si::MPS2 - 4.0 * G).abs() < 0.05);
+    assert!((sample.magnetic_field.x / si::T - 200e-6).abs() < 1e-6);
+}
+
+
+#[test]
+fn configure_rate_and_mode() {
+    let trajectory = |_| Orientation::default().sample(still(), field(20e-6));
+    let simulator = Simulator::new(trajectory, simulator::Config::new());
+    let mut sensor = Lsm303::from_i2c_device(simulator).unwrap();
+
+    let config = Config::new().accelerometer_rate(Rate::Rate5376Hz).mode(OperatingMode::LowPower);
+    sensor.configure(config).unwrap();
+    // 5376 Hz, low-power mode, all axes enabled
+    assert_eq!(sensor.bus().accelerometer_register(CTRL_REG1_A), 0x9f);
+
+    // Neither 1344 Hz in low-power mode nor 5376 Hz in normal mode is
+    // available, so the rate and mode can only be changed together.
+    let config = Config::new().accelerometer_rate(Rate::Rate1344Hz).mode(OperatingMode::Normal);
+    sensor.configure(config).unwrap();
+    assert_eq!(sensor.bus().accelerometer_register(CTRL_REG1_A), 0x97);
+
+    let config = Config::new().accelerometer_rate(Rate::Rate1344Hz).mode(OperatingMode::LowPower);
+    match sensor.configure(config) {
+        Err(Error::RateNotAvailableInMode) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(sensor.bus().accelerometer_register(CTRL_REG1_A), 0x97);
+}
+
+
+#[test]
+fn halves() {
+    let trajectory = |_| Orientation::default().sample(still(), field(20e-6));
+    let simulator = Simulator::new(trajectory, simulator::Config::new());
+    let mut sensor = Lsm303::from_i2c_device(simulator).unwrap();
+    sensor.bus().advance(Duration::from_millis(100));
+
+    assert!(sensor.accelerometer().status().unwrap().xyz_available);
+    assert!(sensor.magnetometer().status().unwrap().ready);
+    assert_eq!(sensor.bus().time(), 0.1 * si::S);
+}
+
+
+#[test]
+fn send_to_thread() {
+    let trajectory = |_| Orientation::default().sample(still(), field(20e-6));
+    let simulator = Simulator::new(trajectory, simulator::Config::new());
+    let mut sensor = Lsm303::from_i2c_device(simulator).unwrap();
+    sensor.bus().advance(Duration::from_millis(100));
+
+    let sample = thread::spawn(move || sensor.read_all().unwrap()).join().unwrap();
+    assert!((sample.acceleration.z / si::MPS2 - G).abs() < 0.01);
+}
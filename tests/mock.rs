@@ -0,0 +1,256 @@
+//! Run the driver against the simulated register file in `lsm303::mock`.
+
+extern crate embedded_hal;
+extern crate lsm303;
+
+use embedded_hal::i2c::I2c;
+use lsm303::accelerometer::{self, AutoScale, Scale};
+use lsm303::magnetometer::{self, AutoGain, Gain};
+use lsm303::mock::Device;
+use lsm303::registers::*;
+use lsm303::units::si;
+use lsm303::{Accelerometer, Error, Magnetometer};
+
+
+#[test]
+fn reset_values() {
+    let device = Device::new();
+    assert_eq!(device.accelerometer_register(CTRL_REG1_A), 0x07);
+    assert_eq!(device.accelerometer_register(CTRL_REG4_A), 0x00);
+    assert_eq!(device.magnetometer_register(CRA_REG_M), 0x10);
+    assert_eq!(device.magnetometer_register(CRB_REG_M), 0x20);
+    assert_eq!(device.magnetometer_register(MR_REG_M), 0x03);
+}
+
+
+#[test]
+fn read_only_registers() {
+    let mut device = Device::new();
+    device.write(0x19, &[STATUS_REG_A, 0xFF]).unwrap();
+    device.write(0x1E, &[IRA_REG_M, 0x00]).unwrap();
+    assert_eq!(device.accelerometer_register(STATUS_REG_A), 0x00);
+    assert_eq!(device.magnetometer_register(IRA_REG_M), b'H');
+}
+
+
+#[test]
+fn auto_increment() {
+    let mut device = Device::new();
+    for (i, register) in (OUT_X_L_A..OUT_Z_H_A + 1).enumerate() {
+        device.set_accelerometer_register(register, i as u8 + 1);
+    }
+
+    let mut data = [0; 6];
+    device.write_read(0x19, &[OUT_X_L_A | 0x80], &mut data).unwrap();
+    assert_eq!(data, [1, 2, 3, 4, 5, 6]);
+
+    // Without the MSB set, the accelerometer reads the same register.
+    device.write_read(0x19, &[OUT_X_L_A], &mut data).unwrap();
+    assert_eq!(data, [1; 6]);
+}
+
+
+#[test]
+fn unknown_address() {
+    let mut device = Device::new();
+    assert!(device.write(0x1D, &[0x00, 0x00]).is_err());
+}
+
+
+#[test]
+fn set_scale() {
+    let mut accel = Accelerometer::from_i2c_device(Device::new()).unwrap();
+    // High resolution is enabled on initialization.
+    assert_eq!(accel.accelerometer_register(CTRL_REG4_A), 0x08);
+
+    accel.set_scale(Scale::Scale16G).unwrap();
+    assert_eq!(accel.accelerometer_register(CTRL_REG4_A), 0x38);
+
+    accel.set_scale(Scale::Scale2G).unwrap();
+    assert_eq!(accel.accelerometer_register(CTRL_REG4_A), 0x08);
+}
+
+
+#[test]
+fn auto_scale() {
+    let mut accel = Accelerometer::from_i2c_device(Device::new()).unwrap();
+    accel.set_auto_scale(Some(AutoScale {
+        saturation: 0.95,
+        fraction: 0.75,
+        samples: 3,
+    }));
+
+    // -2048 counts on X saturates the 2 g scale, so the scale steps up after the reading.
+    accel.set_accelerometer_register(OUT_X_H_A, 0x80);
+    let (a, scale) = accel.read_acceleration_with_scale().unwrap();
+    assert_eq!(scale, Scale::Scale2G);
+    assert!((a.x / si::MPS2 + 2.048 * 9.80665).abs() < 1e-9);
+    assert_eq!(accel.accelerometer_register(CTRL_REG4_A), 0x18);
+
+    // About 1 g is under 75% of the 2 g scale, but the scale only steps down
+    // after three such readings in a row. About 1.7 g in between starts the count again.
+    for &(out_x_h_a, expected) in &[
+        (0x20, 0x18),
+        (0x20, 0x18),
+        (0x34, 0x18),
+        (0x20, 0x18),
+        (0x20, 0x18),
+        (0x20, 0x08),
+    ] {
+        accel.set_accelerometer_register(OUT_X_H_A, out_x_h_a);
+        let (_, scale) = accel.read_acceleration_with_scale().unwrap();
+        assert_eq!(scale, Scale::Scale4G);
+        assert_eq!(accel.accelerometer_register(CTRL_REG4_A), expected);
+    }
+
+    accel.set_accelerometer_register(OUT_X_H_A, 0x20);
+    let (a, scale) = accel.read_acceleration_with_scale().unwrap();
+    assert_eq!(scale, Scale::Scale2G);
+    assert_eq!(accel.scale(), Scale::Scale2G);
+    assert!((a.x / si::MPS2 - 0.512 * 9.80665).abs() < 1e-9);
+}
+
+
+#[test]
+fn auto_scale_at_16g() {
+    let mut accel = Accelerometer::from_i2c_device(Device::new()).unwrap();
+    accel.set_scale(Scale::Scale16G).unwrap();
+    accel.set_auto_scale(Some(AutoScale {
+        saturation: 0.95,
+        fraction: 0.75,
+        samples: 3,
+    }));
+
+    // 750 counts of 12 mg is 9 g, which would saturate the 8 g scale,
+    // so the scale stays at 16 g however long the reading holds.
+    accel.set_accelerometer_register(OUT_X_L_A, 0xE0);
+    accel.set_accelerometer_register(OUT_X_H_A, 0x2E);
+    for _ in 0..10 {
+        let (a, scale) = accel.read_acceleration_with_scale().unwrap();
+        assert_eq!(scale, Scale::Scale16G);
+        assert!((a.x / si::MPS2 - 9.0 * 9.80665).abs() < 1e-9);
+        assert_eq!(accel.accelerometer_register(CTRL_REG4_A), 0x38);
+    }
+}
+
+
+#[test]
+fn set_accelerometer_rate() {
+    let mut accel = Accelerometer::from_i2c_device(Device::new()).unwrap();
+    assert_eq!(accel.accelerometer_register(CTRL_REG1_A), 0x27);
+
+    accel.set_rate(accelerometer::Rate::Rate400Hz).unwrap();
+    assert_eq!(accel.accelerometer_register(CTRL_REG1_A), 0x77);
+}
+
+
+#[test]
+fn set_gain() {
+    let mut mag = Magnetometer::from_i2c_device(Device::new()).unwrap();
+    assert_eq!(mag.magnetometer_register(CRB_REG_M), 0x20);
+
+    mag.set_gain(Gain::Gain_8_1).unwrap();
+    assert_eq!(mag.magnetometer_register(CRB_REG_M), 0xE0);
+}
+
+
+#[test]
+fn auto_gain() {
+    let mut mag = Magnetometer::from_i2c_device(Device::new()).unwrap();
+    mag.set_auto_gain(Some(AutoGain {
+        fraction: 0.75,
+        samples: 3,
+    }));
+
+    // An overflow on X steps the gain up after the reading.
+    mag.set_magnetometer_register(OUT_X_H_M, 0xF0);
+    mag.set_magnetometer_register(OUT_X_L_M, 0x00);
+    match mag.read_magnetic_field_with_gain() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert!(overflow.x),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(mag.magnetometer_register(CRB_REG_M), 0x40);
+
+    // Half a Gauss is under 75% of the 1.3 Gauss range, but the gain only
+    // steps down after three such readings in a row. One Gauss in between
+    // starts the count again.
+    for &((out_x_h_m, out_x_l_m), expected) in &[
+        ((0x01, 0xAB), 0x40),
+        ((0x01, 0xAB), 0x40),
+        ((0x03, 0x57), 0x40),
+        ((0x01, 0xAB), 0x40),
+        ((0x01, 0xAB), 0x40),
+        ((0x01, 0xAB), 0x20),
+    ] {
+        mag.set_magnetometer_register(OUT_X_H_M, out_x_h_m);
+        mag.set_magnetometer_register(OUT_X_L_M, out_x_l_m);
+        let (_, gain) = mag.read_magnetic_field_with_gain().unwrap();
+        assert_eq!(gain, Gain::Gain_1_9);
+        assert_eq!(mag.magnetometer_register(CRB_REG_M), expected);
+    }
+
+    let (field, gain) = mag.read_magnetic_field_with_gain().unwrap();
+    assert_eq!(gain, Gain::Gain_1_3);
+    assert_eq!(mag.gain(), Gain::Gain_1_3);
+    assert!((field.x / si::T - 427.0 / 1100.0 * 1e-4).abs() < 1e-12);
+}
+
+
+#[test]
+fn set_magnetometer_rate() {
+    let mut mag = Magnetometer::from_i2c_device(Device::new()).unwrap();
+    // Temperature enabled, 15 Hz
+    assert_eq!(mag.magnetometer_register(CRA_REG_M), 0x90);
+
+    mag.set_rate(magnetometer::Rate::Rate220Hz).unwrap();
+    assert_eq!(mag.magnetometer_register(CRA_REG_M), 0x9C);
+}
+
+
+#[test]
+fn read_magnetic_field() {
+    let mut device = Device::new();
+    // 1100 counts on X, which is one Gauss at the default gain.
+    device.set_magnetometer_register(OUT_X_H_M, 0x04);
+    device.set_magnetometer_register(OUT_X_L_M, 0x4C);
+
+    let mut mag = Magnetometer::from_i2c_device(device).unwrap();
+    let field = mag.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    assert_eq!(field.y, 0.0 * si::T);
+    assert_eq!(field.z, 0.0 * si::T);
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn probe() {
+    use lsm303::variant::{self, Lsm303dlhc, Model, Variant};
+
+    let found = variant::probe(&mut Device::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].model, Model::Lsm303dlhc);
+    assert_eq!(found[0].addresses, Lsm303dlhc::ADDRESSES);
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn read_when_ready_timeout() {
+    use lsm303::errors::Sensor;
+    use std::time::Duration;
+
+    // The register file never reports new data.
+    let mut accel = Accelerometer::from_i2c_device(Device::new()).unwrap();
+    match accel.read_acceleration_when_ready(Duration::from_millis(10)) {
+        Err(Error::Timeout(Sensor::Accelerometer)) => {}
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected a timeout"),
+    }
+
+    let mut mag = Magnetometer::from_i2c_device(Device::new()).unwrap();
+    match mag.read_magnetic_field_when_ready(Duration::from_millis(10)) {
+        Err(Error::Timeout(Sensor::Magnetometer)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,822 @@
+//! Run the driver against a mock I2C bus.
+//!
+//! These tests do not need any hardware, and run whether or not the crate is built with `std`.
+
+extern crate embedded_hal;
+extern crate embedded_hal_mock;
+extern crate lsm303;
+
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use lsm303::accelerometer::{self, AutoScale, Axis, ClickConfig, ClickEvent, ClickKind,
+                            Combination, FifoMode, HighPassCutoff, HighPassFilter, HighPassMode,
+                            Interrupt, InterruptConfig, OperatingMode, Rate, Scale};
+use lsm303::errors::{Operation, Overflow, Sensor};
+use lsm303::magnetometer::{self, AutoGain, Gain, Mode};
+use lsm303::units::si;
+use lsm303::{Accelerometer, Error, Magnetometer};
+use std::time::Duration;
+
+const ACCEL: u8 = 0x19;
+const MAG: u8 = 0x1E;
+
+const G: f64 = 9.80665;
+
+
+/// The transactions performed by `Accelerometer::from_i2c_device`.
+fn accelerometer_init() -> Vec<Transaction> {
+    vec![
+        // CTRL_REG1_A: 10 Hz, all axes enabled
+        Transaction::write(ACCEL, vec![0x20, 0x27]),
+        // CTRL_REG4_A: high resolution
+        Transaction::write_read(ACCEL, vec![0x23], vec![0x00]),
+        Transaction::write(ACCEL, vec![0x23, 0x08]),
+    ]
+}
+
+
+/// The transactions performed by `Magnetometer::from_i2c_device`.
+fn magnetometer_init() -> Vec<Transaction> {
+    vec![
+        // IRA_REG_M .. IRC_REG_M
+        Transaction::write_read(MAG, vec![0x0A], b"H43".to_vec()),
+        // MR_REG_M: continuous
+        Transaction::write(MAG, vec![0x02, 0x00]),
+        // CRA_REG_M: temperature enabled, 15 Hz
+        Transaction::write(MAG, vec![0x00, 0x90]),
+        // CRB_REG_M: +/- 1.3 Gauss
+        Transaction::write_read(MAG, vec![0x01], vec![0x00]),
+        Transaction::write(MAG, vec![0x01, 0x20]),
+    ]
+}
+
+
+#[test]
+fn read_acceleration() {
+    let mut expectations = accelerometer_init();
+    // 1000 counts on Z, left justified
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x3E],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let a = accel.read_acceleration().unwrap();
+    assert_eq!(a.x, 0.0 * si::MPS2);
+    assert_eq!(a.y, 0.0 * si::MPS2);
+    assert!((a.z / si::MPS2 - 9.80665).abs() < 1e-9);
+
+    accel.done();
+}
+
+
+#[test]
+fn read_raw_acceleration() {
+    let mut expectations = accelerometer_init();
+    // CTRL_REG4_A: +/- 16 g
+    expectations.push(Transaction::write_read(ACCEL, vec![0x23], vec![0x08]));
+    expectations.push(Transaction::write(ACCEL, vec![0x23, 0x38]));
+    // -1000, 1 and 2047 counts, left justified
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x80, 0xC1, 0x10, 0x00, 0xF0, 0x7F],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_scale(Scale::Scale16G).unwrap();
+    let raw = accel.read_raw_acceleration().unwrap();
+    assert_eq!((raw.counts.x, raw.counts.y, raw.counts.z), (-1000, 1, 2047));
+    assert_eq!(raw.scale, Scale::Scale16G);
+    assert_eq!(raw.mode, OperatingMode::HighResolution);
+    // 12 mg per count
+    assert!((raw.to_acceleration().x / si::MPS2 + 12.0 * G).abs() < 1e-9);
+
+    accel.done();
+}
+
+
+#[test]
+fn accelerometer_lsb_size() {
+    // mg per count at 12 bits, then the multiplier for each mode.
+    let scales = [
+        (Scale::Scale2G, 1.0),
+        (Scale::Scale4G, 2.0),
+        (Scale::Scale8G, 4.0),
+        (Scale::Scale16G, 12.0),
+    ];
+    let modes = [
+        (OperatingMode::HighResolution, 1.0),
+        (OperatingMode::Normal, 4.0),
+        (OperatingMode::LowPower, 16.0),
+    ];
+    for &(scale, milli_g) in &scales {
+        for &(mode, factor) in &modes {
+            let lsb = accelerometer::lsb_size(scale, mode);
+            let expected = milli_g * factor * 1e-3 * G;
+            assert!((lsb / si::MPS2 - expected).abs() < 1e-12, "{:?} {:?}", scale, mode);
+        }
+    }
+}
+
+
+#[test]
+fn set_rate() {
+    let mut expectations = accelerometer_init();
+    expectations.push(Transaction::write_read(ACCEL, vec![0x20], vec![0x27]));
+    expectations.push(Transaction::write(ACCEL, vec![0x20, 0x57]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_rate(Rate::Rate100Hz).unwrap();
+
+    accel.done();
+}
+
+
+#[test]
+fn set_rate_unavailable_in_mode() {
+    let mut expectations = accelerometer_init();
+    // Low-power mode is not enabled, so nothing is written.
+    expectations.push(Transaction::write_read(ACCEL, vec![0x20], vec![0x27]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    match accel.set_rate(Rate::Rate5376Hz) {
+        Err(Error::RateNotAvailableInMode) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    accel.done();
+}
+
+
+#[test]
+fn set_low_power_mode() {
+    let mut expectations = accelerometer_init();
+    // CTRL_REG1_A: LPen set
+    expectations.push(Transaction::write_read(ACCEL, vec![0x20], vec![0x27]));
+    expectations.push(Transaction::write(ACCEL, vec![0x20, 0x2F]));
+    // CTRL_REG4_A: HR cleared
+    expectations.push(Transaction::write_read(ACCEL, vec![0x23], vec![0x08]));
+    expectations.push(Transaction::write(ACCEL, vec![0x23, 0x00]));
+    // 64 counts on Z, left justified in 8 bits
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x40],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_mode(OperatingMode::LowPower).unwrap();
+    let raw = accel.read_raw_acceleration().unwrap();
+    assert_eq!(raw.counts.z, 64);
+    assert_eq!(raw.mode, OperatingMode::LowPower);
+    // 16 mg per count
+    let lsb = accelerometer::lsb_size(Scale::Scale2G, OperatingMode::LowPower);
+    assert!((lsb / si::MPS2 - 16e-3 * G).abs() < 1e-12);
+    assert!((raw.to_acceleration().z / si::MPS2 - 1.024 * G).abs() < 1e-9);
+
+    accel.done();
+}
+
+
+#[test]
+fn set_normal_mode() {
+    let mut expectations = accelerometer_init();
+    // CTRL_REG1_A: LPen cleared
+    expectations.push(Transaction::write_read(ACCEL, vec![0x20], vec![0x2F]));
+    expectations.push(Transaction::write(ACCEL, vec![0x20, 0x27]));
+    // CTRL_REG4_A: HR cleared
+    expectations.push(Transaction::write_read(ACCEL, vec![0x23], vec![0x08]));
+    expectations.push(Transaction::write(ACCEL, vec![0x23, 0x00]));
+    // 256 counts on Z, left justified in 10 bits
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x40],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_mode(OperatingMode::Normal).unwrap();
+    let raw = accel.read_raw_acceleration().unwrap();
+    assert_eq!(raw.counts.z, 256);
+    assert_eq!(raw.mode, OperatingMode::Normal);
+    // 4 mg per count
+    let lsb = accelerometer::lsb_size(Scale::Scale2G, OperatingMode::Normal);
+    assert!((lsb / si::MPS2 - 4e-3 * G).abs() < 1e-12);
+    assert!((raw.to_acceleration().z / si::MPS2 - 1.024 * G).abs() < 1e-9);
+
+    accel.done();
+}
+
+
+#[test]
+fn accelerometer_status() {
+    let mut expectations = accelerometer_init();
+    // STATUS_REG_A: ZYXOR, YOR, ZDA and XDA
+    expectations.push(Transaction::write_read(ACCEL, vec![0x27], vec![0xA5]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let status = accel.status().unwrap();
+    assert!(status.xyz_overrun);
+    assert!(!status.x_overrun);
+    assert!(status.y_overrun);
+    assert!(!status.z_overrun);
+    assert!(!status.xyz_available);
+    assert!(status.x_available);
+    assert!(!status.y_available);
+    assert!(status.z_available);
+
+    accel.done();
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn read_acceleration_when_ready() {
+    let mut expectations = accelerometer_init();
+    // STATUS_REG_A: nothing new, then XOR and ZYXDA
+    expectations.push(Transaction::write_read(ACCEL, vec![0x27], vec![0x00]));
+    expectations.push(Transaction::write_read(ACCEL, vec![0x27], vec![0x18]));
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x3E],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let (a, status) = accel.read_acceleration_when_ready(Duration::from_secs(1)).unwrap();
+    assert!((a.z / si::MPS2 - G).abs() < 1e-9);
+    assert!(status.xyz_available);
+    assert!(status.x_overrun);
+    assert!(!status.xyz_overrun);
+
+    accel.done();
+}
+
+
+#[test]
+fn auto_scale_bus_error() {
+    let mut expectations = accelerometer_init();
+    // -2048 counts on X saturates the 2 g scale, but the scale cannot be changed.
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x80, 0x00, 0x00, 0x00, 0x00],
+    ));
+    expectations.push(
+        Transaction::write_read(ACCEL, vec![0x23], vec![0x08]).with_error(ErrorKind::Other),
+    );
+    // The next reading is still at 2 g, and the change is tried again.
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x3E],
+    ));
+    expectations.push(Transaction::write_read(ACCEL, vec![0x23], vec![0x08]));
+    expectations.push(Transaction::write(ACCEL, vec![0x23, 0x18]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_auto_scale(Some(AutoScale::default()));
+
+    let (a, scale) = accel.read_acceleration_with_scale().unwrap();
+    assert_eq!(scale, Scale::Scale2G);
+    assert!((a.x / si::MPS2 + 2.048 * 9.80665).abs() < 1e-9);
+    assert_eq!(accel.pending_scale(), Some(Scale::Scale4G));
+
+    let (a, scale) = accel.read_acceleration_with_scale().unwrap();
+    assert_eq!(scale, Scale::Scale2G);
+    assert!((a.z / si::MPS2 - 9.80665).abs() < 1e-9);
+    assert_eq!(accel.pending_scale(), None);
+    assert_eq!(accel.scale(), Scale::Scale4G);
+
+    accel.done();
+}
+
+
+#[test]
+fn set_fifo_mode() {
+    let mut expectations = accelerometer_init();
+    // CTRL_REG5_A: FIFO_EN set
+    expectations.push(Transaction::write_read(ACCEL, vec![0x24], vec![0x00]));
+    expectations.push(Transaction::write(ACCEL, vec![0x24, 0x40]));
+    // FIFO_CTRL_REG_A: stream mode and a watermark of 31, keeping TR
+    expectations.push(Transaction::write_read(ACCEL, vec![0x2E], vec![0x20]));
+    expectations.push(Transaction::write(ACCEL, vec![0x2E, 0xBF]));
+    // CTRL_REG5_A: FIFO_EN cleared, keeping LIR_INT1
+    expectations.push(Transaction::write_read(ACCEL, vec![0x24], vec![0x48]));
+    expectations.push(Transaction::write(ACCEL, vec![0x24, 0x08]));
+    // FIFO_CTRL_REG_A: bypass mode and a watermark of 0
+    expectations.push(Transaction::write_read(ACCEL, vec![0x2E], vec![0xBF]));
+    expectations.push(Transaction::write(ACCEL, vec![0x2E, 0x20]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    accel.set_fifo_mode(FifoMode::Stream, 31).unwrap();
+    accel.set_fifo_mode(FifoMode::Bypass, 0).unwrap();
+
+    accel.done();
+}
+
+
+#[test]
+fn set_fifo_mode_invalid_watermark() {
+    // Nothing is written.
+    let expectations = accelerometer_init();
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    match accel.set_fifo_mode(FifoMode::Fifo, 32) {
+        Err(Error::InvalidFifoWatermark) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    accel.done();
+}
+
+
+#[test]
+fn read_fifo() {
+    let mut expectations = accelerometer_init();
+    // FIFO_SRC_REG_A: two samples
+    expectations.push(Transaction::write_read(ACCEL, vec![0x2F], vec![0x02]));
+    expectations.push(Transaction::write_read(
+        ACCEL,
+        vec![0x28 | 0x80],
+        vec![
+            0x00, 0x00, 0x00, 0x00, 0x80, 0x3E,
+            0x80, 0x3E, 0x00, 0x00, 0x00, 0x00,
+        ],
+    ));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let fifo = accel.read_fifo().unwrap();
+    assert!(!fifo.overrun);
+    assert_eq!(fifo.samples().len(), 2);
+    assert!((fifo.samples()[0].z / si::MPS2 - 9.80665).abs() < 1e-9);
+    assert!((fifo.samples()[1].x / si::MPS2 - 9.80665).abs() < 1e-9);
+
+    accel.done();
+}
+
+
+#[test]
+fn bus_error() {
+    let mut expectations = accelerometer_init();
+    expectations.push(
+        Transaction::write_read(ACCEL, vec![0x20], vec![0x00]).with_error(ErrorKind::Other),
+    );
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    match accel.set_rate(Rate::Rate100Hz) {
+        Err(Error::Bus { sensor, operation, register, error }) => {
+            assert_eq!(sensor, Sensor::Accelerometer);
+            assert_eq!(operation, Operation::Read);
+            assert_eq!(register, 0x20);
+            assert_eq!(error, ErrorKind::Other);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    accel.done();
+}
+
+
+#[test]
+fn configure_interrupt() {
+    let mut expectations = accelerometer_init();
+    // INT1_THS_A: 20 steps of 16 mg at 2 g
+    expectations.push(Transaction::write(ACCEL, vec![0x32, 0x14]));
+    // INT1_DURATION_A: 5 samples at 10 Hz
+    expectations.push(Transaction::write(ACCEL, vec![0x33, 0x05]));
+    // INT1_CFG_A: AOI, ZLIE, YLIE, XLIE
+    expectations.push(Transaction::write(ACCEL, vec![0x30, 0x95]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let config = InterruptConfig::new()
+        .combination(Combination::And)
+        .x_low(true)
+        .y_low(true)
+        .z_low(true)
+        .threshold(0.32 * G * si::MPS2)
+        .duration(0.5 * si::S);
+    accel.configure_interrupt(Interrupt::Int1, config).unwrap();
+
+    accel.done();
+}
+
+
+#[test]
+fn configure_interrupt_out_of_range() {
+    // Nothing is written if the configuration cannot be represented.
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&accelerometer_init())).unwrap();
+
+    // 128 steps of 16 mg at 2 g
+    let config = InterruptConfig::new().threshold(2.048 * G * si::MPS2);
+    match accel.configure_interrupt(Interrupt::Int1, config) {
+        Err(Error::ThresholdOutOfRange) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 128 samples at 10 Hz
+    let config = InterruptConfig::new().duration(12.8 * si::S);
+    match accel.configure_interrupt(Interrupt::Int1, config) {
+        Err(Error::DurationOutOfRange) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    accel.done();
+}
+
+
+#[test]
+fn interrupt_source() {
+    let mut expectations = accelerometer_init();
+    // INT1_SRC_A: IA, YH, XH
+    expectations.push(Transaction::write_read(ACCEL, vec![0x31], vec![0x4A]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let source = accel.interrupt_source(Interrupt::Int1).unwrap();
+    assert!(source.active);
+    assert!(source.x_high);
+    assert!(!source.x_low);
+    assert!(source.y_high);
+    assert!(!source.y_low);
+    assert!(!source.z_high);
+    assert!(!source.z_low);
+
+    accel.done();
+}
+
+
+#[test]
+fn configure_click() {
+    let mut expectations = accelerometer_init();
+    // CLICK_THS_A: 31 steps of 16 mg at 2 g
+    expectations.push(Transaction::write(ACCEL, vec![0x3A, 0x1F]));
+    // TIME_LIMIT_A, TIME_LATENCY_A, TIME_WINDOW_A: in samples at 10 Hz
+    expectations.push(Transaction::write(ACCEL, vec![0x3B, 0x05]));
+    expectations.push(Transaction::write(ACCEL, vec![0x3C, 0x0A]));
+    expectations.push(Transaction::write(ACCEL, vec![0x3D, 0x14]));
+    // CLICK_CFG_A: ZD, ZS
+    expectations.push(Transaction::write(ACCEL, vec![0x38, 0x30]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let config = ClickConfig::new()
+        .z_single(true)
+        .z_double(true)
+        .threshold(0.5 * G * si::MPS2)
+        .time_limit(Duration::from_millis(500))
+        .latency(Duration::from_secs(1))
+        .window(Duration::from_secs(2));
+    accel.configure_click(config).unwrap();
+
+    accel.done();
+}
+
+
+#[test]
+fn configure_click_out_of_range() {
+    // Nothing is written if the configuration cannot be represented.
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&accelerometer_init())).unwrap();
+
+    // 128 steps of 16 mg at 2 g
+    let config = ClickConfig::new().threshold(2.048 * G * si::MPS2);
+    match accel.configure_click(config) {
+        Err(Error::ThresholdOutOfRange) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 128 samples at 10 Hz
+    let config = ClickConfig::new().time_limit(Duration::from_millis(12_800));
+    match accel.configure_click(config) {
+        Err(Error::DurationOutOfRange) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 256 samples at 10 Hz
+    let config = ClickConfig::new().window(Duration::from_millis(25_600));
+    match accel.configure_click(config) {
+        Err(Error::DurationOutOfRange) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    accel.done();
+}
+
+
+#[test]
+fn read_click_event() {
+    let mut expectations = accelerometer_init();
+    // CLICK_SRC_A: IA, DCLICK, Sign, Y
+    expectations.push(Transaction::write_read(ACCEL, vec![0x39], vec![0x6A]));
+    // CLICK_SRC_A: no interrupt
+    expectations.push(Transaction::write_read(ACCEL, vec![0x39], vec![0x00]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    match accel.read_click_event().unwrap() {
+        Some(ClickEvent {
+            axis: Axis::Y,
+            negative: true,
+            kind: ClickKind::Double,
+        }) => {}
+        _ => panic!("expected a negative double click on Y"),
+    }
+    assert!(accel.read_click_event().unwrap().is_none());
+
+    accel.done();
+}
+
+
+#[test]
+fn set_high_pass_filter() {
+    let mut expectations = accelerometer_init();
+    // CTRL_REG2_A: HPM1, HPM0, HPCF2, HPCLICK, HPIS1
+    expectations.push(Transaction::write(ACCEL, vec![0x21, 0xE5]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let filter = HighPassFilter::new()
+        .mode(HighPassMode::AutoReset)
+        .cutoff(HighPassCutoff::Low)
+        .click(true)
+        .interrupt1(true);
+    accel.set_high_pass_filter(filter).unwrap();
+
+    accel.done();
+}
+
+
+#[test]
+fn reset_high_pass_filter() {
+    let mut expectations = accelerometer_init();
+    // REFERENCE_A
+    expectations.push(Transaction::write_read(ACCEL, vec![0x26], vec![0x5A]));
+
+    let mut accel = Accelerometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    assert_eq!(accel.reset_high_pass_filter().unwrap(), 0x5A);
+
+    accel.done();
+}
+
+
+#[test]
+fn read_magnetic_field() {
+    let mut expectations = magnetometer_init();
+    // Registers are ordered X, Z, Y
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0x04, 0x4C, 0x03, 0xD4, 0x00, 0x00],
+    ));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let field = mag.read_magnetic_field().unwrap();
+    // 1100 and 980 counts are one Gauss
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    assert_eq!(field.y, 0.0 * si::T);
+    assert!((field.z / si::T - 1e-4).abs() < 1e-12);
+
+    mag.done();
+}
+
+
+#[test]
+fn set_magnetometer_mode() {
+    let mut expectations = magnetometer_init();
+    // MR_REG_M
+    expectations.push(Transaction::write(MAG, vec![0x02, 0x01]));
+    expectations.push(Transaction::write(MAG, vec![0x02, 0x03]));
+    expectations.push(Transaction::write(MAG, vec![0x02, 0x00]));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    mag.set_mode(Mode::Single).unwrap();
+    mag.set_mode(Mode::Sleep).unwrap();
+    mag.set_mode(Mode::Continuous).unwrap();
+
+    mag.done();
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn measure_once_discards_stale_data() {
+    let mut expectations = magnetometer_init();
+    // SR_REG_M: data from the continuous mode is still waiting to be read.
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x01]));
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0x08, 0x98, 0x00, 0x00, 0x00, 0x00],
+    ));
+    // MR_REG_M: single conversion
+    expectations.push(Transaction::write(MAG, vec![0x02, 0x01]));
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x00]));
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x01]));
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0x04, 0x4C, 0x00, 0x00, 0x00, 0x00],
+    ));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let field = mag.measure_once().unwrap();
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+
+    mag.done();
+}
+
+
+#[test]
+fn read_raw_magnetic_field() {
+    let mut expectations = magnetometer_init();
+    // CRB_REG_M: +/- 4.0 Gauss
+    expectations.push(Transaction::write_read(MAG, vec![0x01], vec![0x20]));
+    expectations.push(Transaction::write(MAG, vec![0x01, 0x80]));
+    // -2048 on X, 400 on Z and 450 on Y
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0xF8, 0x00, 0x01, 0x90, 0x01, 0xC2],
+    ));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    mag.set_gain(Gain::Gain_4_0).unwrap();
+    let raw = mag.read_raw_magnetic_field().unwrap();
+    assert_eq!((raw.counts.x, raw.counts.y, raw.counts.z), (-2048, 450, 400));
+    assert_eq!(raw.gain, Gain::Gain_4_0);
+    // 450 and 400 counts are one Gauss
+    let field = raw.to_magnetic_field().unwrap();
+    assert!((field.y / si::T - 1e-4).abs() < 1e-12);
+    assert!((field.z / si::T - 1e-4).abs() < 1e-12);
+
+    mag.done();
+}
+
+
+#[test]
+fn magnetometer_lsb_size() {
+    // LSB per Gauss on X and Y, and on Z
+    let gains = [
+        (Gain::Gain_1_3, 1100.0, 980.0),
+        (Gain::Gain_1_9, 855.0, 760.0),
+        (Gain::Gain_2_5, 670.0, 600.0),
+        (Gain::Gain_4_0, 450.0, 400.0),
+        (Gain::Gain_4_7, 400.0, 355.0),
+        (Gain::Gain_5_6, 330.0, 295.0),
+        (Gain::Gain_8_1, 230.0, 205.0),
+    ];
+    for &(gain, xy, z) in &gains {
+        let (lsb_xy, lsb_z) = magnetometer::lsb_size(gain);
+        assert!((lsb_xy / si::T - 1e-4 / xy).abs() < 1e-15, "{:?}", gain);
+        assert!((lsb_z / si::T - 1e-4 / z).abs() < 1e-15, "{:?}", gain);
+    }
+}
+
+
+#[test]
+fn read_temperature() {
+    let mut expectations = magnetometer_init();
+    // TEMP_OUT_H_M, TEMP_OUT_L_M: -40 counts, left justified
+    expectations.push(Transaction::write_read(MAG, vec![0x31], vec![0xFD, 0x80]));
+    // 16 counts
+    expectations.push(Transaction::write_read(MAG, vec![0x31], vec![0x01, 0x00]));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    // 8 counts per degree, below the default of 20 °C
+    let temperature = mag.read_temperature().unwrap();
+    assert!((temperature / si::K - 288.15).abs() < 1e-9);
+
+    mag.set_temperature_offset(296.0 * si::K);
+    let temperature = mag.read_temperature().unwrap();
+    assert!((temperature / si::K - 298.0).abs() < 1e-9);
+
+    mag.done();
+}
+
+
+#[test]
+fn magnetometer_status() {
+    let mut expectations = magnetometer_init();
+    // SR_REG_M: LOCK, then DRDY
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x02]));
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x01]));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let status = mag.status().unwrap();
+    assert!(status.lock);
+    assert!(!status.ready);
+    let status = mag.status().unwrap();
+    assert!(!status.lock);
+    assert!(status.ready);
+
+    mag.done();
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn read_magnetic_field_when_ready() {
+    let mut expectations = magnetometer_init();
+    // SR_REG_M: nothing new, then DRDY
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x00]));
+    expectations.push(Transaction::write_read(MAG, vec![0x09], vec![0x01]));
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0x04, 0x4C, 0x00, 0x00, 0x00, 0x00],
+    ));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let field = mag.read_magnetic_field_when_ready(Duration::from_secs(1)).unwrap();
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+
+    mag.done();
+}
+
+
+#[test]
+fn magnetometer_overflow() {
+    let mut expectations = magnetometer_init();
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0xF0, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    match mag.read_magnetic_field() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert_eq!(
+            overflow,
+            Overflow { x: true, y: false, z: false }
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    mag.done();
+}
+
+
+#[test]
+fn auto_gain_bus_error() {
+    let mut expectations = magnetometer_init();
+    // An overflow on X, but the gain cannot be changed.
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0xF0, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ));
+    expectations.push(
+        Transaction::write_read(MAG, vec![0x01], vec![0x20]).with_error(ErrorKind::Other),
+    );
+    // The next reading is still at 1.3 Gauss, and the change is tried again.
+    expectations.push(Transaction::write_read(
+        MAG,
+        vec![0x03],
+        vec![0x04, 0x4C, 0x00, 0x00, 0x00, 0x00],
+    ));
+    expectations.push(Transaction::write_read(MAG, vec![0x01], vec![0x20]));
+    expectations.push(Transaction::write(MAG, vec![0x01, 0x40]));
+
+    let mut mag = Magnetometer::from_i2c_device(Mock::new(&expectations)).unwrap();
+    mag.set_auto_gain(Some(AutoGain::default()));
+
+    // The overflow is reported rather than the bus error.
+    match mag.read_magnetic_field_with_gain() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert!(overflow.x),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(mag.pending_gain(), Some(Gain::Gain_1_9));
+
+    let (field, gain) = mag.read_magnetic_field_with_gain().unwrap();
+    assert_eq!(gain, Gain::Gain_1_3);
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    assert_eq!(mag.pending_gain(), None);
+    assert_eq!(mag.gain(), Gain::Gain_1_9);
+
+    mag.done();
+}
+
+
+#[test]
+fn wrong_device() {
+    let expectations = [Transaction::write_read(MAG, vec![0x0A], vec![0x00, 0x00, 0x00])];
+
+    let mut device = Mock::new(&expectations);
+    match Magnetometer::from_i2c_device(device.clone()) {
+        Err(Error::WrongDevice(id)) => assert_eq!(id, [0, 0, 0]),
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected an error"),
+    }
+
+    device.done();
+}
+
+
+#[test]
+fn unchecked_device() {
+    // The same as magnetometer_init, without reading the identification registers.
+    // Any read of them would fail the test, whatever ID the bus returned.
+    let expectations = &magnetometer_init()[1..];
+
+    let mut mag = Magnetometer::from_i2c_device_unchecked(Mock::new(expectations)).unwrap();
+
+    mag.done();
+}
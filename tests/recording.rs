@@ -0,0 +1,105 @@
+//! Record a session against a mock I2C bus, and replay it.
+
+#![cfg(feature = "std")]
+
+extern crate embedded_hal;
+extern crate embedded_hal_mock;
+extern crate lsm303;
+
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use lsm303::accelerometer::Rate;
+use lsm303::recording::{Recorder, Replay, ReplayError};
+use lsm303::{Accelerometer, Error};
+
+const ACCEL: u8 = 0x19;
+
+
+/// Initialization, followed by a reading of 1000 counts on Z.
+fn session() -> Vec<Transaction> {
+    vec![
+        Transaction::write(ACCEL, vec![0x20, 0x27]),
+        Transaction::write_read(ACCEL, vec![0x23], vec![0x00]),
+        Transaction::write(ACCEL, vec![0x23, 0x08]),
+        Transaction::write_read(
+            ACCEL,
+            vec![0x28 | 0x80],
+            vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x3E],
+        ),
+    ]
+}
+
+
+#[test]
+fn record_and_replay() {
+    let mut mock = Mock::new(&session());
+    let mut log = Vec::new();
+    let recorded = {
+        let device = Recorder::new(mock.clone(), &mut log).unwrap();
+        let mut accel = Accelerometer::from_i2c_device(device).unwrap();
+        accel.read_acceleration().unwrap()
+    };
+    mock.done();
+
+    let text = String::from_utf8(log.clone()).unwrap();
+    assert_eq!(text.lines().count(), 5);
+    assert!(text.lines().last().unwrap().ends_with(" 19 w:a8 r:00000000803e"));
+
+    let replay = Replay::from_reader(&log[..]).unwrap();
+    assert_eq!(replay.remaining(), 4);
+    assert_eq!(replay.timestamp(), None);
+
+    let mut accel = Accelerometer::from_i2c_device(replay).unwrap();
+    assert_eq!(accel.read_acceleration().unwrap(), recorded);
+    assert_eq!(accel.remaining(), 0);
+    assert!(accel.timestamp().is_some());
+
+    match accel.read_acceleration() {
+        Err(Error::Bus { error: ReplayError::EndOfRecording, .. }) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+
+#[test]
+fn replay_error() {
+    let log = "\
+# lsm303 i2c recording
+1760000000.000001 19 w:2027
+1760000000.000002 19 w:23 r:00 err:nack-data
+";
+    let replay = Replay::from_reader(log.as_bytes()).unwrap();
+    match Accelerometer::from_i2c_device(replay) {
+        Err(Error::Bus { register, error: ReplayError::Recorded(kind), .. }) => {
+            assert_eq!(register, 0x23);
+            assert_eq!(kind, ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+
+#[test]
+fn replay_mismatch() {
+    let log = "\
+1760000000.000001 19 w:2027
+1760000000.000002 19 w:23 r:00
+1760000000.000003 19 w:2308
+1760000000.000004 19 w:2057
+";
+    let replay = Replay::from_reader(log.as_bytes()).unwrap();
+    let mut accel = Accelerometer::from_i2c_device(replay).unwrap();
+    // The recording sets 100 Hz without reading CTRL_REG1_A first.
+    match accel.set_rate(Rate::Rate100Hz) {
+        Err(Error::Bus { error: ReplayError::Mismatch { line }, .. }) => assert_eq!(line, 4),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+
+#[test]
+fn invalid_recording() {
+    assert!(Replay::from_reader("1760000000.000000 19 x:00\n".as_bytes()).is_err());
+    assert!(Replay::from_reader("1760000000.000000 19 w:0\n".as_bytes()).is_err());
+}
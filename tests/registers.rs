@@ -0,0 +1,212 @@
+//! Check the register map against the LSM303DLHC datasheet.
+//!
+//! The tables in this file are transcribed directly from
+//! Table 17 (register address map) and Section 7 (register description)
+//! of the datasheet, independently of `src/registers.rs`.
+
+extern crate lsm303;
+
+use lsm303::registers::*;
+
+
+/// Compare register constants against their datasheet addresses.
+macro_rules! check_addresses {
+    ( $( $name:ident => $address:expr, )* ) => {{
+        $(
+            assert_eq!(
+                $name, $address,
+                "{} should be at 0x{:02X}", stringify!($name), $address
+            );
+        )*
+        vec![$( $name ),*]
+    }}
+}
+
+
+/// Compare bitflags against their datasheet bit positions.
+///
+/// Also checks that the flags cover exactly the listed bits,
+/// which catches two flags declared on the same bit.
+macro_rules! check_flags {
+    ( $( $register:ident { $( $flag:ident => $bit:expr, )* } )* ) => {
+        $(
+            let mut expected = 0u8;
+            $(
+                assert_eq!(
+                    $register::$flag.bits(), 1 << $bit,
+                    "{}::{} should be bit {}",
+                    stringify!($register), stringify!($flag), $bit
+                );
+                expected |= 1 << $bit;
+            )*
+            assert_eq!(
+                $register::all().bits(), expected,
+                "{} does not cover the expected bits", stringify!($register)
+            );
+        )*
+    }
+}
+
+
+fn assert_unique(addresses: &[u8]) {
+    let mut sorted = addresses.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), addresses.len(), "duplicate register address");
+}
+
+
+#[test]
+fn accelerometer_addresses() {
+    let addresses = check_addresses! {
+        CTRL_REG1_A => 0x20,
+        CTRL_REG2_A => 0x21,
+        CTRL_REG3_A => 0x22,
+        CTRL_REG4_A => 0x23,
+        CTRL_REG5_A => 0x24,
+        CTRL_REG6_A => 0x25,
+        REFERENCE_A => 0x26,
+        STATUS_REG_A => 0x27,
+        OUT_X_L_A => 0x28,
+        OUT_X_H_A => 0x29,
+        OUT_Y_L_A => 0x2A,
+        OUT_Y_H_A => 0x2B,
+        OUT_Z_L_A => 0x2C,
+        OUT_Z_H_A => 0x2D,
+        FIFO_CTRL_REG_A => 0x2E,
+        FIFO_SRC_REG_A => 0x2F,
+        INT1_CFG_A => 0x30,
+        INT1_SOURCE_A => 0x31,
+        INT1_THS_A => 0x32,
+        INT1_DURATION_A => 0x33,
+        INT2_CFG_A => 0x34,
+        INT2_SOURCE_A => 0x35,
+        INT2_THS_A => 0x36,
+        INT2_DURATION_A => 0x37,
+        CLICK_CFG_A => 0x38,
+        CLICK_SRC_A => 0x39,
+        CLICK_THS_A => 0x3A,
+        TIME_LIMIT_A => 0x3B,
+        TIME_LATENCY_A => 0x3C,
+        TIME_WINDOW_A => 0x3D,
+    };
+    assert_unique(&addresses);
+}
+
+
+#[test]
+fn magnetometer_addresses() {
+    let addresses = check_addresses! {
+        CRA_REG_M => 0x00,
+        CRB_REG_M => 0x01,
+        MR_REG_M => 0x02,
+        OUT_X_H_M => 0x03,
+        OUT_X_L_M => 0x04,
+        OUT_Z_H_M => 0x05,
+        OUT_Z_L_M => 0x06,
+        OUT_Y_H_M => 0x07,
+        OUT_Y_L_M => 0x08,
+        SR_REG_M => 0x09,
+        IRA_REG_M => 0x0A,
+        IRB_REG_M => 0x0B,
+        IRC_REG_M => 0x0C,
+        TEMP_OUT_H_M => 0x31,
+        TEMP_OUT_L_M => 0x32,
+    };
+    assert_unique(&addresses);
+}
+
+
+#[test]
+fn accelerometer_flags() {
+    check_flags! {
+        CtrlReg1A {
+            ODR3 => 7, ODR2 => 6, ODR1 => 5, ODR0 => 4,
+            LPen => 3, Zen => 2, Yen => 1, Xen => 0,
+        }
+        CtrlReg2A {
+            HPM1 => 7, HPM0 => 6, HPCF2 => 5, HPCF1 => 4,
+            FDS => 3, HPCLICK => 2, HPIS2 => 1, HPIS1 => 0,
+        }
+        CtrlReg3A {
+            I1_CLICK => 7, I1_AOI1 => 6, I1_AOI2 => 5, I1_DRDY1 => 4,
+            I1_DRDY2 => 3, I1_WTM => 2, I1_OVERRUN => 1,
+        }
+        CtrlReg4A {
+            BDU => 7, BLE => 6, FS1 => 5, FS0 => 4,
+            HR => 3, SIM => 0,
+        }
+        CtrlReg5A {
+            BOOT => 7, FIFO_EN => 6,
+            LIR_INT1 => 3, D4D_INT1 => 2, LIR_INT2 => 1, D4D_INT2 => 0,
+        }
+        CtrlReg6A {
+            I2_CLICK => 7, I2_INT1 => 6, I2_INT2 => 5, BOOT_I1 => 4,
+            P2_ACT => 3, H_LACTIVE => 1,
+        }
+        Reference {
+            Ref7 => 7, Ref6 => 6, Ref5 => 5, Ref4 => 4,
+            Ref3 => 3, Ref2 => 2, Ref1 => 1, Ref0 => 0,
+        }
+        StatusRegA {
+            ZYXOR => 7, ZOR => 6, YOR => 5, XOR => 4,
+            ZYXDA => 3, ZDA => 2, YDA => 1, XDA => 0,
+        }
+        FifoCtrlRegA {
+            FM1 => 7, FM0 => 6, TR => 5, FTH4 => 4,
+            FTH3 => 3, FTH2 => 2, FTH1 => 1, FTH0 => 0,
+        }
+        FifoSrcRegA {
+            WTM => 7, OVRN_FIFO => 6, EMPTY => 5, FSS4 => 4,
+            FSS3 => 3, FSS2 => 2, FSS1 => 1, FSS0 => 0,
+        }
+        IntCfgA {
+            AOI => 7, _6D => 6, ZHIE => 5, ZLIE => 4,
+            YHIE => 3, YLIE => 2, XHIE => 1, XLIE => 0,
+        }
+        IntSrcA {
+            IA => 6, ZH => 5, ZL => 4,
+            YH => 3, YL => 2, XH => 1, XL => 0,
+        }
+        IntDurationA {
+            D6 => 6, D5 => 5, D4 => 4,
+            D3 => 3, D2 => 2, D1 => 1, D0 => 0,
+        }
+        ClickCfgA {
+            ZD => 5, ZS => 4,
+            YD => 3, YS => 2, XD => 1, XS => 0,
+        }
+        ClickSrcA {
+            IA_click => 6, DCLICK => 5, SCLICK => 4,
+            Sign => 3, Z => 2, Y => 1, X => 0,
+        }
+    }
+}
+
+
+#[test]
+fn magnetometer_flags() {
+    check_flags! {
+        CraRegM {
+            TEMP_EN => 7, DO2 => 4, DO1 => 3, DO0 => 2,
+        }
+        CrbRegM {
+            GN2 => 7, GN1 => 6, GN0 => 5,
+        }
+        MrRegM {
+            MD1 => 1, MD0 => 0,
+        }
+        SrRegM {
+            LOCK => 1, DRDY => 0,
+        }
+    }
+}
+
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_names() {
+    assert_eq!(OUT_X_H_H, OUT_X_H_A);
+    assert_eq!(CtrlReg2Af::HPIS1, CtrlReg2A::HPIS1);
+    assert_eq!(IntCfgA::ZLIO, IntCfgA::ZLIE);
+}
@@ -0,0 +1,265 @@
+//! Run the driver against the physics-driven simulator.
+
+extern crate lsm303;
+
+use lsm303::accelerometer::{AccelerationVector, FifoMode, Scale};
+use lsm303::common::Vector3;
+use lsm303::magnetometer::{Gain, MagneticField};
+use lsm303::registers::MR_REG_M;
+use lsm303::simulator::{Config, Orientation, Sample, Simulator};
+use lsm303::units::{si, value};
+use lsm303::{Accelerometer, Error, Magnetometer};
+use std::f64::consts::PI;
+use std::time::Duration;
+
+
+const G: f64 = 9.80665;
+
+
+fn still() -> AccelerationVector {
+    let zero = 0.0 * si::MPS2;
+    Vector3 { x: zero, y: zero, z: zero }
+}
+
+
+/// A field pointing north and down, in the world frame.
+fn earth_field() -> MagneticField {
+    Vector3 {
+        x: 20e-6 * si::T,
+        y: 0.0 * si::T,
+        z: -40e-6 * si::T,
+    }
+}
+
+
+fn at_rest(orientation: Orientation) -> impl FnMut(si::Second<f64>) -> Sample {
+    move |_| orientation.sample(still(), earth_field())
+}
+
+
+#[test]
+fn tilt() {
+    let orientation = Orientation {
+        roll: 0.3,
+        pitch: -0.2,
+        yaw: 1.0,
+    };
+    let simulator = Simulator::new(at_rest(orientation), Config::new());
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+    accel.advance(Duration::from_millis(100));
+
+    let a = accel.read_acceleration().unwrap();
+    let (x, y, z) = (value(a.x / si::MPS2), value(a.y / si::MPS2), value(a.z / si::MPS2));
+    let roll = y.atan2(z);
+    let pitch = (-x).atan2((y * y + z * z).sqrt());
+    assert!((roll - 0.3).abs() < 0.01, "roll: {}", roll);
+    assert!((pitch + 0.2).abs() < 0.01, "pitch: {}", pitch);
+}
+
+
+#[test]
+fn heading() {
+    let orientation = Orientation {
+        roll: 0.0,
+        pitch: 0.0,
+        yaw: PI / 6.0,
+    };
+    let simulator = Simulator::new(at_rest(orientation), Config::new());
+    let mut mag = Magnetometer::from_i2c_device(simulator).unwrap();
+    mag.advance(Duration::from_millis(100));
+
+    let field = mag.read_magnetic_field().unwrap();
+    let heading = (-value(field.y / si::T)).atan2(value(field.x / si::T));
+    assert!((heading - PI / 6.0).abs() < 0.01, "heading: {}", heading);
+    assert!((field.z / si::T + 40e-6).abs() < 1e-6);
+}
+
+
+#[test]
+fn motion() {
+    // Accelerating north at 1 m/s² for one second, then stopping.
+    let trajectory = |t: si::Second<f64>| {
+        let mut acceleration = still();
+        if t < 1.0 * si::S {
+            acceleration.x = 1.0 * si::MPS2;
+        }
+        Orientation::default().sample(acceleration, earth_field())
+    };
+    let simulator = Simulator::new(trajectory, Config::new());
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+
+    accel.advance(Duration::from_millis(500));
+    assert!((accel.read_acceleration().unwrap().x / si::MPS2 - 1.0).abs() < 0.01);
+    accel.advance(Duration::from_millis(1000));
+    assert!((accel.read_acceleration().unwrap().x / si::MPS2).abs() < 0.01);
+}
+
+
+#[test]
+fn saturation() {
+    let trajectory = |_| {
+        let mut acceleration = still();
+        acceleration.x = 5.0 * G * si::MPS2;
+        Orientation::default().sample(acceleration, earth_field())
+    };
+    let simulator = Simulator::new(trajectory, Config::new());
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+
+    accel.advance(Duration::from_millis(100));
+    let x = accel.read_acceleration().unwrap().x / si::MPS2;
+    assert!((x - 2.0 * G).abs() < 0.01, "x: {}", x);
+
+    accel.set_scale(Scale::Scale8G).unwrap();
+    accel.advance(Duration::from_millis(100));
+    let x = accel.read_acceleration().unwrap().x / si::MPS2;
+    assert!((x - 5.0 * G).abs() < 0.05, "x: {}", x);
+}
+
+
+#[test]
+fn magnetometer_overflow() {
+    // 2 Gauss is beyond the default gain of 1.3 Gauss.
+    let trajectory = |_| {
+        let field = Vector3 {
+            x: 200e-6 * si::T,
+            y: 0.0 * si::T,
+            z: 0.0 * si::T,
+        };
+        Orientation::default().sample(still(), field)
+    };
+    let simulator = Simulator::new(trajectory, Config::new());
+    let mut mag = Magnetometer::from_i2c_device(simulator).unwrap();
+
+    mag.advance(Duration::from_millis(100));
+    match mag.read_magnetic_field() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert!(overflow.x && !overflow.y),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    mag.set_gain(Gain::Gain_2_5).unwrap();
+    mag.advance(Duration::from_millis(100));
+    let field = mag.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 200e-6).abs() < 1e-6);
+}
+
+
+#[test]
+fn magnetometer_full_scale() {
+    // 1.5 Gauss is within the range of the output at 1.3 Gauss,
+    // but beyond the full scale of the gain.
+    let trajectory = |t: si::Second<f64>| {
+        let x = if t < 1.0 * si::S { 120e-6 } else { 150e-6 };
+        let field = Vector3 {
+            x: x * si::T,
+            y: 0.0 * si::T,
+            z: 0.0 * si::T,
+        };
+        Orientation::default().sample(still(), field)
+    };
+    let simulator = Simulator::new(trajectory, Config::new());
+    let mut mag = Magnetometer::from_i2c_device(simulator).unwrap();
+
+    mag.advance(Duration::from_millis(100));
+    let field = mag.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 120e-6).abs() < 1e-6);
+
+    mag.advance(Duration::from_secs(1));
+    match mag.read_magnetic_field() {
+        Err(Error::MagnetometerOverflow(overflow)) => assert!(overflow.x && !overflow.y),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+
+#[test]
+fn data_ready() {
+    let simulator = Simulator::new(at_rest(Orientation::default()), Config::new());
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+    assert!(!accel.status().unwrap().xyz_available);
+
+    // The default rate is 10 Hz.
+    accel.advance(Duration::from_millis(50));
+    assert!(!accel.status().unwrap().xyz_available);
+    accel.advance(Duration::from_millis(50));
+    assert!(accel.status().unwrap().xyz_available);
+
+    accel.read_acceleration().unwrap();
+    assert!(!accel.status().unwrap().xyz_available);
+
+    // Two samples without a read in between.
+    accel.advance(Duration::from_millis(200));
+    assert!(accel.status().unwrap().xyz_overrun);
+}
+
+
+#[test]
+fn fifo() {
+    let simulator = Simulator::new(at_rest(Orientation::default()), Config::new());
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+    accel.set_fifo_mode(FifoMode::Stream, 8).unwrap();
+
+    accel.advance(Duration::from_millis(500));
+    let fifo = accel.read_fifo().unwrap();
+    assert_eq!(fifo.samples().len(), 5);
+    assert!(!fifo.watermark);
+    assert!(!fifo.overrun);
+    for sample in fifo.samples() {
+        assert!((sample.z / si::MPS2 - G).abs() < 0.01);
+    }
+    assert_eq!(accel.read_fifo().unwrap().samples().len(), 0);
+
+    accel.advance(Duration::from_secs(5));
+    let fifo = accel.read_fifo().unwrap();
+    assert_eq!(fifo.samples().len(), 32);
+    assert!(fifo.watermark);
+    assert!(fifo.overrun);
+}
+
+
+#[test]
+fn noise_and_bias() {
+    let bias = Vector3 {
+        x: 0.5 * si::MPS2,
+        y: 0.0 * si::MPS2,
+        z: 0.0 * si::MPS2,
+    };
+    // 0.1 m/s² RMS at 10 Hz.
+    let config = Config::new()
+        .accelerometer_noise_density(0.1 / 5.0f64.sqrt())
+        .accelerometer_bias(bias)
+        .seed(7);
+    let simulator = Simulator::new(at_rest(Orientation::default()), config);
+    let mut accel = Accelerometer::from_i2c_device(simulator).unwrap();
+
+    let n = 1000;
+    let mut sum = 0.0;
+    let mut sum_squares = 0.0;
+    for _ in 0..n {
+        accel.advance(Duration::from_millis(100));
+        let x = value(accel.read_acceleration().unwrap().x / si::MPS2);
+        sum += x;
+        sum_squares += x * x;
+    }
+    let mean = sum / n as f64;
+    let deviation = (sum_squares / n as f64 - mean * mean).sqrt();
+    assert!((mean - 0.5).abs() < 0.02, "mean: {}", mean);
+    assert!((deviation - 0.1).abs() < 0.01, "deviation: {}", deviation);
+}
+
+
+#[test]
+fn measure_once() {
+    // Let the polling in `measure_once` advance the clock.
+    let config = Config::new()
+        .transaction_time(Duration::from_millis(5))
+        .temperature(303.15 * si::K);
+    let simulator = Simulator::new(at_rest(Orientation::default()), config);
+    let mut mag = Magnetometer::from_i2c_device(simulator).unwrap();
+
+    let field = mag.measure_once().unwrap();
+    assert!((field.x / si::T - 20e-6).abs() < 1e-6);
+    assert_eq!(mag.magnetometer_register(MR_REG_M), 0x03);
+
+    let temperature = mag.read_temperature().unwrap();
+    assert!((temperature / si::K - 303.15).abs() < 0.2);
+}
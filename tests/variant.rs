@@ -0,0 +1,255 @@
+//! Run the variant-generic driver against a mock I2C bus.
+//!
+//! These tests do not need any hardware, and run with or without `std`.
+
+extern crate embedded_hal;
+extern crate embedded_hal_mock;
+extern crate lsm303;
+
+#[cfg(feature = "std")]
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use lsm303::accelerometer::Scale;
+use lsm303::units::si;
+use lsm303::variant::{Addresses, Driver, Lsm303agr, Lsm303c, Lsm303d, Lsm303dlhc};
+#[cfg(feature = "std")]
+use lsm303::variant::{probe, Detected, Model};
+use lsm303::Error;
+
+const G: f64 = 9.80665;
+
+
+fn celsius(value: f64) -> si::Kelvin<f64> {
+    (value + 273.15) * si::K
+}
+
+
+#[test]
+fn lsm303dlhc() {
+    let expectations = [
+        Transaction::write_read(0x1E, vec![0x0A], b"H43".to_vec()),
+        Transaction::write(0x19, vec![0x20, 0x27]),
+        Transaction::write(0x19, vec![0x23, 0x08]),
+        Transaction::write(0x1E, vec![0x02, 0x00]),
+        Transaction::write(0x1E, vec![0x00, 0x90]),
+        Transaction::write(0x1E, vec![0x01, 0x20]),
+        // 1000 counts on Z, left justified
+        Transaction::write_read(0x19, vec![0x28 | 0x80], vec![0, 0, 0, 0, 0x80, 0x3E]),
+        // 1100 counts on X, ordered X, Z, Y
+        Transaction::write_read(0x1E, vec![0x03], vec![0x04, 0x4C, 0, 0, 0, 0]),
+        // 40 counts, left justified
+        Transaction::write_read(0x1E, vec![0x31], vec![0x02, 0x80]),
+    ];
+
+    let mut sensor: Driver<_, Lsm303dlhc> = Driver::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let a = sensor.read_acceleration().unwrap();
+    assert!((a.z / si::MPS2 - G).abs() < 1e-9);
+    let field = sensor.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    assert!(((sensor.read_temperature().unwrap() - celsius(25.0)) / si::K).abs() < 1e-9);
+
+    sensor.done();
+}
+
+
+#[test]
+fn lsm303d() {
+    let expectations = [
+        Transaction::write_read(0x1D, vec![0x0F], vec![0x49]),
+        Transaction::write(0x1D, vec![0x20, 0x57]),
+        Transaction::write(0x1D, vec![0x21, 0x00]),
+        Transaction::write(0x1D, vec![0x24, 0xF0]),
+        Transaction::write(0x1D, vec![0x25, 0x20]),
+        Transaction::write(0x1D, vec![0x26, 0x00]),
+        // 16384 counts on Z
+        Transaction::write_read(0x1D, vec![0x28 | 0x80], vec![0, 0, 0, 0, 0x00, 0x40]),
+        // 6250 counts on X, which is one Gauss
+        Transaction::write_read(0x1D, vec![0x08 | 0x80], vec![0x6A, 0x18, 0, 0, 0, 0]),
+        // 40 counts
+        Transaction::write_read(0x1D, vec![0x05 | 0x80], vec![0x28, 0x00]),
+        // +/- 8G
+        Transaction::write_read(0x1D, vec![0x21], vec![0x00]),
+        Transaction::write(0x1D, vec![0x21, 0x18]),
+    ];
+
+    let mut sensor: Driver<_, Lsm303d> = Driver::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let a = sensor.read_acceleration().unwrap();
+    assert!((a.z / si::MPS2 - 16384.0 * 0.061e-3 * G).abs() < 1e-9);
+    let field = sensor.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 1e-4).abs() < 1e-12);
+    assert!(((sensor.read_temperature().unwrap() - celsius(30.0)) / si::K).abs() < 1e-9);
+
+    sensor.set_scale(Scale::Scale8G).unwrap();
+    assert_eq!(sensor.scale(), Scale::Scale8G);
+
+    sensor.done();
+}
+
+
+#[test]
+fn lsm303d_sa0_low() {
+    let expectations = [
+        Transaction::write_read(0x1E, vec![0x0F], vec![0x49]),
+        Transaction::write(0x1E, vec![0x20, 0x57]),
+        Transaction::write(0x1E, vec![0x21, 0x00]),
+        Transaction::write(0x1E, vec![0x24, 0xF0]),
+        Transaction::write(0x1E, vec![0x25, 0x20]),
+        Transaction::write(0x1E, vec![0x26, 0x00]),
+    ];
+
+    let addresses = Addresses {
+        accelerometer: 0x1E,
+        magnetometer: 0x1E,
+    };
+    let mut sensor: Driver<_, Lsm303d> =
+        Driver::from_i2c_device_at(Mock::new(&expectations), addresses).unwrap();
+    assert_eq!(sensor.addresses(), addresses);
+
+    sensor.done();
+}
+
+
+#[test]
+fn lsm303agr() {
+    let expectations = [
+        Transaction::write_read(0x19, vec![0x0F], vec![0x33]),
+        Transaction::write_read(0x1E, vec![0x4F], vec![0x40]),
+        Transaction::write(0x19, vec![0x20, 0x27]),
+        Transaction::write(0x19, vec![0x23, 0x88]),
+        Transaction::write(0x19, vec![0x1F, 0xC0]),
+        Transaction::write(0x1E, vec![0x60, 0x80]),
+        Transaction::write(0x1E, vec![0x62, 0x10]),
+        // 1000 counts on Z, left justified
+        Transaction::write_read(0x19, vec![0x28 | 0x80], vec![0, 0, 0, 0, 0x80, 0x3E]),
+        // 1000 counts on X
+        Transaction::write_read(0x1E, vec![0x68 | 0x80], vec![0xE8, 0x03, 0, 0, 0, 0]),
+        // 5 °C above 25 °C
+        Transaction::write_read(0x19, vec![0x0C | 0x80], vec![0x00, 0x05]),
+    ];
+
+    let mut sensor: Driver<_, Lsm303agr> = Driver::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let a = sensor.read_acceleration().unwrap();
+    assert!((a.z / si::MPS2 - 0.98 * G).abs() < 1e-9);
+    let field = sensor.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 1.5e-4).abs() < 1e-12);
+    assert!(((sensor.read_temperature().unwrap() - celsius(30.0)) / si::K).abs() < 1e-9);
+
+    sensor.done();
+}
+
+
+/// The transactions performed by `Driver::<_, Lsm303c>::from_i2c_device`.
+fn lsm303c_init() -> Vec<Transaction> {
+    vec![
+        Transaction::write_read(0x1D, vec![0x0F], vec![0x41]),
+        Transaction::write_read(0x1E, vec![0x0F], vec![0x3D]),
+        Transaction::write(0x1D, vec![0x20, 0x1F]),
+        Transaction::write(0x1D, vec![0x23, 0x04]),
+        Transaction::write(0x1E, vec![0x20, 0xF0]),
+        Transaction::write(0x1E, vec![0x21, 0x60]),
+        Transaction::write(0x1E, vec![0x22, 0x00]),
+        Transaction::write(0x1E, vec![0x23, 0x0C]),
+        Transaction::write(0x1E, vec![0x24, 0x40]),
+    ]
+}
+
+
+#[test]
+fn lsm303c() {
+    let mut expectations = lsm303c_init();
+    expectations.extend(vec![
+        // 16384 counts on Z
+        Transaction::write_read(0x1D, vec![0x28], vec![0, 0, 0, 0, 0x00, 0x40]),
+        // 1000 counts on X
+        Transaction::write_read(0x1E, vec![0x28 | 0x80], vec![0xE8, 0x03, 0, 0, 0, 0]),
+        // 16 counts
+        Transaction::write_read(0x1E, vec![0x2E | 0x80], vec![0x10, 0x00]),
+    ]);
+
+    let mut sensor: Driver<_, Lsm303c> = Driver::from_i2c_device(Mock::new(&expectations)).unwrap();
+    let a = sensor.read_acceleration().unwrap();
+    assert!((a.z / si::MPS2 - 16384.0 * 0.061e-3 * G).abs() < 1e-9);
+    let field = sensor.read_magnetic_field().unwrap();
+    assert!((field.x / si::T - 0.58e-4).abs() < 1e-12);
+    assert!(((sensor.read_temperature().unwrap() - celsius(27.0)) / si::K).abs() < 1e-9);
+
+    sensor.done();
+}
+
+
+#[test]
+fn scale_not_available() {
+    let mut sensor: Driver<_, Lsm303c> = Driver::from_i2c_device(Mock::new(&lsm303c_init())).unwrap();
+
+    match sensor.set_scale(Scale::Scale16G) {
+        Err(Error::ScaleNotAvailable) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(sensor.scale(), Scale::Scale2G);
+
+    sensor.done();
+}
+
+
+#[test]
+fn wrong_device() {
+    // An LSM303AGR is not an LSM303C.
+    let expectations = [Transaction::write_read(0x1D, vec![0x0F], vec![0x33])];
+    let mut mock = Mock::new(&expectations);
+
+    match Driver::<_, Lsm303c>::from_i2c_device(mock.clone()) {
+        Err(Error::WrongDevice(id)) => assert_eq!(id, [0x33, 0, 0]),
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("expected an error"),
+    }
+
+    mock.done();
+}
+
+
+#[test]
+#[cfg(feature = "std")]
+fn probe_bus() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    // An LSM303AGR, and an LSM303D with SA0 high.
+    let expectations = [
+        Transaction::write_read(0x1E, vec![0x0A], vec![0, 0, 0]),
+        Transaction::write_read(0x1E, vec![0x4F], vec![0x40]),
+        Transaction::write_read(0x1E, vec![0x0F], vec![0x00]),
+        Transaction::write_read(0x19, vec![0x0F], vec![0x33]),
+        Transaction::write_read(0x1D, vec![0x0F], vec![0x49]),
+    ];
+    let mut mock = Mock::new(&expectations);
+    assert_eq!(
+        probe(&mut mock),
+        vec![
+            Detected {
+                model: Model::Lsm303agr,
+                addresses: Addresses {
+                    accelerometer: 0x19,
+                    magnetometer: 0x1E,
+                },
+            },
+            Detected {
+                model: Model::Lsm303d,
+                addresses: Addresses {
+                    accelerometer: 0x1D,
+                    magnetometer: 0x1D,
+                },
+            },
+        ]
+    );
+    mock.done();
+
+    // Nothing on the bus.
+    let expectations = [
+        Transaction::write_read(0x1E, vec![0x0A], vec![0, 0, 0]).with_error(nack),
+        Transaction::write_read(0x1E, vec![0x4F], vec![0]).with_error(nack),
+        Transaction::write_read(0x1E, vec![0x0F], vec![0]).with_error(nack),
+        Transaction::write_read(0x19, vec![0x0F], vec![0]).with_error(nack),
+        Transaction::write_read(0x1D, vec![0x0F], vec![0]).with_error(nack),
+    ];
+    let mut mock = Mock::new(&expectations);
+    assert_eq!(probe(&mut mock), vec![]);
+    mock.done();
+}